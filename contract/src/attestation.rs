@@ -0,0 +1,697 @@
+use crate::*;
+use base64::Engine;
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+
+// Intel DCAP ECDSA quotes: version 3 (SGX) and version 4 (SGX or TDX).
+// Layout reference: Intel SGX ECDSA Quote Library API, appendix A.
+
+const HEADER_LEN: usize = 48;
+const SGX_REPORT_LEN: usize = 384;
+const TDX_REPORT_LEN: usize = 584;
+const SIGNATURE_LEN: usize = 64;
+const ATTESTATION_KEY_LEN: usize = 64;
+
+const ATT_KEY_TYPE_ECDSA_P256: u16 = 2;
+const TEE_TYPE_SGX: u32 = 0x00;
+const TEE_TYPE_TDX: u32 = 0x81;
+const CERT_DATA_PCK_CHAIN: u16 = 5;
+const CERT_DATA_QE_REPORT: u16 = 6;
+
+// Offsets inside an SGX report body
+const SGX_ATTRIBUTES: usize = 48;
+const SGX_MR_ENCLAVE: usize = 64;
+const SGX_MR_SIGNER: usize = 128;
+const SGX_ISV_PROD_ID: usize = 256;
+const SGX_ISV_SVN: usize = 258;
+const SGX_REPORT_DATA: usize = 320;
+
+// Offsets inside a TDX TD report body
+const TDX_TD_ATTRIBUTES: usize = 120;
+const TDX_MR_TD: usize = 136;
+const TDX_REPORT_DATA: usize = 520;
+
+// Debug bits in the first byte of ATTRIBUTES / TD_ATTRIBUTES. A debug enclave's
+// memory can be read by the host, so its quotes prove nothing.
+const SGX_FLAG_DEBUG: u8 = 0x02;
+const TDX_ATTRIBUTE_DEBUG: u8 = 0x01;
+
+/// Evaluated Intel collateral that quotes are checked against. Governance
+/// keeps it in sync with the current TCB info, QE identity and CRLs.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AttestationCollateral {
+    /// Minimum ISV SVN of the Quoting Enclave.
+    pub min_qe_svn: u16,
+    /// Minimum SVN of the Provisioning Certification Enclave. Checked for v3
+    /// quotes only.
+    pub min_pce_svn: u16,
+    /// Expected MRSIGNER of the Quoting Enclave, hex. Empty skips the check.
+    pub qe_mr_signer: String,
+    /// Serial numbers (hex) of revoked certificates in the PCK chain.
+    pub revoked_serials: Vec<String>,
+}
+
+/// Enclave builds accepted for a code hash. A quote passes when its
+/// measurement is listed or it was signed by `signer.mr_signer`; the ISV
+/// product ID and SVN limits apply either way.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EnclavePolicy {
+    /// Approved MRENCLAVE (SGX) or MRTD (TDX) values, lowercase hex.
+    pub measurements: Vec<String>,
+    pub signer: SignerPolicy,
+}
+
+/// Identity checks on SGX enclaves. TDX quotes carry no ISV identity, so they
+/// fail any product ID or SVN requirement.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SignerPolicy {
+    /// MRSIGNER (hex) whose enclaves are accepted whatever their measurement.
+    pub mr_signer: Option<String>,
+    pub isv_prod_id: Option<u16>,
+    pub min_isv_svn: u16,
+}
+
+impl EnclavePolicy {
+    pub fn check(&self, quote: &VerifiedQuote) -> Result<(), &'static str> {
+        let measurement = to_hex(&quote.measurement);
+        let signer_approved = match (&self.signer.mr_signer, &quote.mr_signer) {
+            (Some(expected), Some(actual)) => to_hex(actual).eq_ignore_ascii_case(expected),
+            _ => false,
+        };
+        if !signer_approved
+            && !self
+                .measurements
+                .iter()
+                .any(|approved| approved.eq_ignore_ascii_case(&measurement))
+        {
+            return Err("Attestation measurement mismatch");
+        }
+        if let Some(prod_id) = self.signer.isv_prod_id {
+            if quote.isv_prod_id != Some(prod_id) {
+                return Err("Enclave product ID mismatch");
+            }
+        }
+        if self.signer.min_isv_svn > 0 && quote.isv_svn.unwrap_or(0) < self.signer.min_isv_svn {
+            return Err("Enclave SVN below minimum");
+        }
+        Ok(())
+    }
+}
+
+pub struct VerifiedQuote {
+    /// MRENCLAVE for SGX, MRTD for TDX.
+    pub measurement: Vec<u8>,
+    pub report_data: [u8; 64],
+    /// SGX only.
+    pub mr_signer: Option<Vec<u8>>,
+    pub isv_prod_id: Option<u16>,
+    pub isv_svn: Option<u16>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| "Quote is truncated".to_string())?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn certification_data(&mut self) -> Result<(u16, &'a [u8]), String> {
+        let data_type = self.u16()?;
+        let size = self.u32()? as usize;
+        Ok((data_type, self.take(size)?))
+    }
+}
+
+// Checks the quote's signature chain up to an approved root and returns the
+// measurement and report data it attests to.
+pub fn verify_quote(
+    quote: &[u8],
+    approved_roots: &UnorderedSet<CryptoHash>,
+    collateral: &AttestationCollateral,
+    now: u64,
+) -> Result<VerifiedQuote, String> {
+    let mut reader = Reader::new(quote);
+    let header = reader.take(HEADER_LEN)?;
+    let version = u16::from_le_bytes([header[0], header[1]]);
+    let att_key_type = u16::from_le_bytes([header[2], header[3]]);
+    let tee_type = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    // Only v3 headers carry the PCE SVN; in v4 these bytes are reserved and
+    // the PCE SVN lives in the PCK certificate's SGX extensions, which are
+    // not parsed here.
+    let pce_svn = (version == 3).then(|| u16::from_le_bytes([header[10], header[11]]));
+
+    let report_len = match (version, tee_type) {
+        (3, _) | (4, TEE_TYPE_SGX) => SGX_REPORT_LEN,
+        (4, TEE_TYPE_TDX) => TDX_REPORT_LEN,
+        _ => return Err("Unsupported quote version".to_string()),
+    };
+    if att_key_type != ATT_KEY_TYPE_ECDSA_P256 {
+        return Err("Unsupported attestation key type".to_string());
+    }
+
+    let body = reader.take(report_len)?;
+    let signed_len = HEADER_LEN + report_len;
+    let signature_len = reader.u32()? as usize;
+    let mut signature_data = Reader::new(reader.take(signature_len)?);
+
+    let quote_signature = signature_data.take(SIGNATURE_LEN)?;
+    let attestation_key = signature_data.take(ATTESTATION_KEY_LEN)?;
+    if version == 4 {
+        // v4 wraps the QE report in a certification data entry
+        let (data_type, data) = signature_data.certification_data()?;
+        if data_type != CERT_DATA_QE_REPORT {
+            return Err("Unsupported certification data".to_string());
+        }
+        signature_data = Reader::new(data);
+    }
+    let qe_report = signature_data.take(SGX_REPORT_LEN)?;
+    let qe_signature = signature_data.take(SIGNATURE_LEN)?;
+    let qe_auth_len = signature_data.u16()? as usize;
+    let qe_auth = signature_data.take(qe_auth_len)?;
+    let (data_type, cert_chain) = signature_data.certification_data()?;
+    if data_type != CERT_DATA_PCK_CHAIN {
+        return Err("Unsupported certification data".to_string());
+    }
+
+    let chain = parse_pem_chain(cert_chain)?;
+    let pck_key = verify_certificate_chain(&chain, approved_roots, collateral, now)?;
+
+    // The PCK key signs the QE report, which commits to the attestation key
+    verify_raw_signature(&pck_key, qe_report, qe_signature)
+        .map_err(|_| "Invalid QE report signature".to_string())?;
+    let qe_report_data = &qe_report[SGX_REPORT_DATA..];
+    let key_hash = env::sha256(&[attestation_key, qe_auth].concat());
+    if qe_report_data[..32] != key_hash[..] || qe_report_data[32..].iter().any(|b| *b != 0) {
+        return Err("QE report does not bind the attestation key".to_string());
+    }
+
+    let qe_svn = u16::from_le_bytes([qe_report[SGX_ISV_SVN], qe_report[SGX_ISV_SVN + 1]]);
+    if qe_svn < collateral.min_qe_svn {
+        return Err("QE security version below minimum".to_string());
+    }
+    if pce_svn.is_some_and(|svn| svn < collateral.min_pce_svn) {
+        return Err("PCE security version below minimum".to_string());
+    }
+    if !collateral.qe_mr_signer.is_empty()
+        && !to_hex(&qe_report[SGX_MR_SIGNER..SGX_MR_SIGNER + 32])
+            .eq_ignore_ascii_case(&collateral.qe_mr_signer)
+    {
+        return Err("Unexpected QE signer".to_string());
+    }
+
+    // The attestation key signs the header and report body
+    let attestation_key = VerifyingKey::from_sec1_bytes(&[&[0x04], attestation_key].concat())
+        .map_err(|_| "Invalid attestation key".to_string())?;
+    verify_raw_signature(&attestation_key, &quote[..signed_len], quote_signature)
+        .map_err(|_| "Invalid quote signature".to_string())?;
+
+    if report_len == TDX_REPORT_LEN {
+        if body[TDX_TD_ATTRIBUTES] & TDX_ATTRIBUTE_DEBUG != 0 {
+            return Err("Debug enclaves are not accepted".to_string());
+        }
+        return Ok(VerifiedQuote {
+            measurement: body[TDX_MR_TD..TDX_MR_TD + 48].to_vec(),
+            report_data: body[TDX_REPORT_DATA..TDX_REPORT_DATA + 64]
+                .try_into()
+                .unwrap(),
+            mr_signer: None,
+            isv_prod_id: None,
+            isv_svn: None,
+        });
+    }
+
+    if body[SGX_ATTRIBUTES] & SGX_FLAG_DEBUG != 0 {
+        return Err("Debug enclaves are not accepted".to_string());
+    }
+    Ok(VerifiedQuote {
+        measurement: body[SGX_MR_ENCLAVE..SGX_MR_ENCLAVE + 32].to_vec(),
+        report_data: body[SGX_REPORT_DATA..SGX_REPORT_DATA + 64]
+            .try_into()
+            .unwrap(),
+        mr_signer: Some(body[SGX_MR_SIGNER..SGX_MR_SIGNER + 32].to_vec()),
+        isv_prod_id: Some(u16::from_le_bytes([
+            body[SGX_ISV_PROD_ID],
+            body[SGX_ISV_PROD_ID + 1],
+        ])),
+        isv_svn: Some(u16::from_le_bytes([
+            body[SGX_ISV_SVN],
+            body[SGX_ISV_SVN + 1],
+        ])),
+    })
+}
+
+// Verifies leaf to root, each certificate against the next one's key. The
+// root is trusted by hash; the returned key is the leaf (PCK) key.
+fn verify_certificate_chain(
+    chain: &[Vec<u8>],
+    approved_roots: &UnorderedSet<CryptoHash>,
+    collateral: &AttestationCollateral,
+    now: u64,
+) -> Result<VerifyingKey, String> {
+    if chain.len() < 2 {
+        return Err("Certificate chain is too short".to_string());
+    }
+    let root = chain.last().unwrap();
+    if !approved_roots.contains(&env::sha256_array(root)) {
+        return Err("Root certificate not approved".to_string());
+    }
+
+    let certificates = chain
+        .iter()
+        .map(|der| parse_certificate(der))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (i, certificate) in certificates.iter().enumerate() {
+        if now < certificate.not_before || now > certificate.not_after {
+            return Err("Certificate is expired or not yet valid".to_string());
+        }
+        let serial = to_hex(trim_leading_zeros(certificate.serial));
+        if collateral
+            .revoked_serials
+            .iter()
+            .any(|revoked| revoked.eq_ignore_ascii_case(&serial))
+        {
+            return Err("Certificate has been revoked".to_string());
+        }
+
+        if let Some(issuer) = certificates.get(i + 1) {
+            if certificate.issuer != issuer.subject {
+                return Err("Certificate chain is broken".to_string());
+            }
+            let issuer_key = VerifyingKey::from_sec1_bytes(issuer.public_key)
+                .map_err(|_| "Invalid certificate key".to_string())?;
+            let signature = Signature::from_der(certificate.signature)
+                .map_err(|_| "Invalid certificate signature".to_string())?;
+            issuer_key
+                .verify_prehash(&env::sha256(certificate.tbs), &signature)
+                .map_err(|_| "Invalid certificate signature".to_string())?;
+        }
+    }
+
+    VerifyingKey::from_sec1_bytes(certificates[0].public_key)
+        .map_err(|_| "Invalid PCK certificate key".to_string())
+}
+
+fn verify_raw_signature(key: &VerifyingKey, message: &[u8], signature: &[u8]) -> Result<(), ()> {
+    let signature = Signature::from_slice(signature).map_err(|_| ())?;
+    key.verify_prehash(&env::sha256(message), &signature)
+        .map_err(|_| ())
+}
+
+fn parse_pem_chain(data: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    let text = std::str::from_utf8(data)
+        .map_err(|_| "Invalid certificate chain encoding".to_string())?
+        .trim_end_matches('\0');
+
+    let mut chain = Vec::new();
+    for block in text.split("-----BEGIN CERTIFICATE-----").skip(1) {
+        let body = block
+            .split("-----END CERTIFICATE-----")
+            .next()
+            .unwrap_or_default();
+        let encoded: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+        let der = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|_| "Invalid certificate chain encoding".to_string())?;
+        chain.push(der);
+    }
+    Ok(chain)
+}
+
+pub struct Certificate<'a> {
+    tbs: &'a [u8],
+    serial: &'a [u8],
+    issuer: &'a [u8],
+    subject: &'a [u8],
+    not_before: u64,
+    not_after: u64,
+    public_key: &'a [u8],
+    signature: &'a [u8],
+}
+
+struct DerElement<'a> {
+    tag: u8,
+    content: &'a [u8],
+    // The whole element, header included
+    raw: &'a [u8],
+    rest: &'a [u8],
+}
+
+fn der_element(input: &[u8]) -> Result<DerElement<'_>, String> {
+    let malformed = || "Malformed certificate".to_string();
+    let tag = *input.first().ok_or_else(malformed)?;
+    let first = *input.get(1).ok_or_else(malformed)?;
+    let (len, header_len) = if first < 0x80 {
+        (first as usize, 2)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 {
+            return Err(malformed());
+        }
+        let bytes = input.get(2..2 + count).ok_or_else(malformed)?;
+        let len = bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        (len, 2 + count)
+    };
+    let end = header_len.checked_add(len).ok_or_else(malformed)?;
+    if end > input.len() {
+        return Err(malformed());
+    }
+    Ok(DerElement {
+        tag,
+        content: &input[header_len..end],
+        raw: &input[..end],
+        rest: &input[end..],
+    })
+}
+
+fn expect_element(input: &[u8], expected: u8) -> Result<DerElement<'_>, String> {
+    let element = der_element(input)?;
+    if element.tag != expected {
+        return Err("Malformed certificate".to_string());
+    }
+    Ok(element)
+}
+
+pub fn parse_certificate(der: &[u8]) -> Result<Certificate<'_>, String> {
+    const SEQUENCE: u8 = 0x30;
+    const INTEGER: u8 = 0x02;
+    const BIT_STRING: u8 = 0x03;
+    const EXPLICIT_VERSION: u8 = 0xa0;
+
+    let certificate = expect_element(der, SEQUENCE)?;
+    let tbs = expect_element(certificate.content, SEQUENCE)?;
+    let algorithm = expect_element(tbs.rest, SEQUENCE)?;
+    let signature = expect_element(algorithm.rest, BIT_STRING)?;
+
+    let mut fields = tbs.content;
+    if fields.first() == Some(&EXPLICIT_VERSION) {
+        fields = der_element(fields)?.rest;
+    }
+    let serial = expect_element(fields, INTEGER)?;
+    let signature_algorithm = expect_element(serial.rest, SEQUENCE)?;
+    let issuer = expect_element(signature_algorithm.rest, SEQUENCE)?;
+    let validity = expect_element(issuer.rest, SEQUENCE)?;
+    let subject = expect_element(validity.rest, SEQUENCE)?;
+    let spki = expect_element(subject.rest, SEQUENCE)?;
+
+    let (not_before, rest) = parse_time(validity.content)?;
+    let (not_after, _) = parse_time(rest)?;
+    let key_algorithm = expect_element(spki.content, SEQUENCE)?;
+    let public_key = expect_element(key_algorithm.rest, BIT_STRING)?;
+
+    // Bit strings start with an unused-bits byte
+    Ok(Certificate {
+        tbs: tbs.raw,
+        serial: serial.content,
+        issuer: issuer.raw,
+        subject: subject.raw,
+        not_before,
+        not_after,
+        public_key: public_key.content.get(1..).unwrap_or_default(),
+        signature: signature.content.get(1..).unwrap_or_default(),
+    })
+}
+
+// UTCTime or GeneralizedTime in `Z` form, as nanoseconds since the epoch
+fn parse_time(input: &[u8]) -> Result<(u64, &[u8]), String> {
+    const UTC_TIME: u8 = 0x17;
+    const GENERALIZED_TIME: u8 = 0x18;
+
+    let DerElement {
+        tag, content, rest, ..
+    } = der_element(input)?;
+    let digits = content
+        .strip_suffix(b"Z")
+        .filter(|d| d.iter().all(u8::is_ascii_digit))
+        .ok_or_else(|| "Malformed certificate time".to_string())?;
+    let number = |range: std::ops::Range<usize>| -> u64 {
+        digits[range]
+            .iter()
+            .fold(0, |acc, d| acc * 10 + (d - b'0') as u64)
+    };
+
+    let (year, offset) = match (tag, digits.len()) {
+        (UTC_TIME, 12) => {
+            let year = number(0..2);
+            (if year >= 50 { 1900 + year } else { 2000 + year }, 2)
+        }
+        (GENERALIZED_TIME, 14) => (number(0..4), 4),
+        _ => return Err("Malformed certificate time".to_string()),
+    };
+    let days = days_from_civil(
+        year,
+        number(offset..offset + 2),
+        number(offset + 2..offset + 4),
+    );
+    let seconds = days * 86_400
+        + number(offset + 4..offset + 6) * 3_600
+        + number(offset + 6..offset + 8) * 60
+        + number(offset + 8..offset + 10);
+    Ok((seconds.saturating_mul(1_000_000_000), rest))
+}
+
+// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    (era * 146_097 + day_of_era).saturating_sub(719_468)
+}
+
+fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Builds certificates and quotes signed by fixed test keys
+#[cfg(test)]
+pub mod test_utils {
+    use super::*;
+    use p256::ecdsa::signature::Signer;
+    use p256::ecdsa::SigningKey;
+
+    const ECDSA_WITH_SHA256: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+    const EC_PUBLIC_KEY: &[u8] = &[0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+    const PRIME256V1: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if content.len() < 0x80 {
+            out.push(content.len() as u8);
+        } else {
+            out.push(0x82);
+            out.extend((content.len() as u16).to_be_bytes());
+        }
+        out.extend(content);
+        out
+    }
+
+    fn name(common_name: &str) -> Vec<u8> {
+        let attribute = [
+            &[0x06, 0x03, 0x55, 0x04, 0x03][..],
+            &tlv(0x0c, common_name.as_bytes()),
+        ]
+        .concat();
+        tlv(0x30, &tlv(0x31, &tlv(0x30, &attribute)))
+    }
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32].into()).unwrap()
+    }
+
+    fn public_key(key: &SigningKey) -> Vec<u8> {
+        key.verifying_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec()
+    }
+
+    pub fn certificate(
+        serial: u8,
+        issuer: &str,
+        subject: &str,
+        subject_key: &SigningKey,
+        issuer_key: &SigningKey,
+    ) -> Vec<u8> {
+        let validity = [tlv(0x17, b"700101000000Z"), tlv(0x18, b"20991231235959Z")].concat();
+        let spki = [
+            tlv(0x30, &[EC_PUBLIC_KEY, PRIME256V1].concat()),
+            tlv(0x03, &[&[0x00], &public_key(subject_key)[..]].concat()),
+        ]
+        .concat();
+        let tbs = tlv(
+            0x30,
+            &[
+                tlv(0xa0, &tlv(0x02, &[2])),
+                tlv(0x02, &[serial]),
+                tlv(0x30, ECDSA_WITH_SHA256),
+                name(issuer),
+                tlv(0x30, &validity),
+                name(subject),
+                tlv(0x30, &spki),
+            ]
+            .concat(),
+        );
+        let signature: Signature = issuer_key.sign(&tbs);
+        let signature = signature.to_der();
+        tlv(
+            0x30,
+            &[
+                tbs,
+                tlv(0x30, ECDSA_WITH_SHA256),
+                tlv(0x03, &[&[0x00], signature.as_bytes()].concat()),
+            ]
+            .concat(),
+        )
+    }
+
+    pub fn root_certificate() -> Vec<u8> {
+        let root_key = signing_key(1);
+        certificate(1, "Test Root CA", "Test Root CA", &root_key, &root_key)
+    }
+
+    fn pem(der: &[u8]) -> String {
+        format!(
+            "-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----\n",
+            base64::engine::general_purpose::STANDARD.encode(der)
+        )
+    }
+
+    // SGX v3 quote over a report body carrying `measurement` and `report_data`
+    pub fn sgx_quote(measurement: [u8; 32], report_data: [u8; 64]) -> Vec<u8> {
+        sgx_quote_signed_by(measurement, [0; 32], 0, 0, report_data)
+    }
+
+    pub fn sgx_quote_signed_by(
+        measurement: [u8; 32],
+        mr_signer: [u8; 32],
+        isv_prod_id: u16,
+        isv_svn: u16,
+        report_data: [u8; 64],
+    ) -> Vec<u8> {
+        let mut body = vec![0u8; SGX_REPORT_LEN];
+        body[SGX_MR_ENCLAVE..SGX_MR_ENCLAVE + 32].copy_from_slice(&measurement);
+        body[SGX_MR_SIGNER..SGX_MR_SIGNER + 32].copy_from_slice(&mr_signer);
+        body[SGX_ISV_PROD_ID..SGX_ISV_PROD_ID + 2].copy_from_slice(&isv_prod_id.to_le_bytes());
+        body[SGX_ISV_SVN..SGX_ISV_SVN + 2].copy_from_slice(&isv_svn.to_le_bytes());
+        body[SGX_REPORT_DATA..].copy_from_slice(&report_data);
+        signed_quote(3, TEE_TYPE_SGX, body)
+    }
+
+    // SGX quote from an enclave built with debugging enabled
+    pub fn sgx_debug_quote(measurement: [u8; 32], report_data: [u8; 64]) -> Vec<u8> {
+        let mut body = vec![0u8; SGX_REPORT_LEN];
+        body[SGX_ATTRIBUTES] = SGX_FLAG_DEBUG;
+        body[SGX_MR_ENCLAVE..SGX_MR_ENCLAVE + 32].copy_from_slice(&measurement);
+        body[SGX_REPORT_DATA..].copy_from_slice(&report_data);
+        signed_quote(3, TEE_TYPE_SGX, body)
+    }
+
+    // TDX v4 quote over a TD report carrying `mr_td`, `debug` setting the
+    // TD_ATTRIBUTES debug bit
+    pub fn tdx_quote(mr_td: [u8; 48], debug: bool, report_data: [u8; 64]) -> Vec<u8> {
+        let mut body = vec![0u8; TDX_REPORT_LEN];
+        if debug {
+            body[TDX_TD_ATTRIBUTES] = TDX_ATTRIBUTE_DEBUG;
+        }
+        body[TDX_MR_TD..TDX_MR_TD + 48].copy_from_slice(&mr_td);
+        body[TDX_REPORT_DATA..].copy_from_slice(&report_data);
+        signed_quote(4, TEE_TYPE_TDX, body)
+    }
+
+    fn signed_quote(version: u16, tee_type: u32, body: Vec<u8>) -> Vec<u8> {
+        let root_key = signing_key(1);
+        let pck_key = signing_key(2);
+        let attestation_key = signing_key(3);
+
+        let chain = pem(&certificate(
+            2,
+            "Test Root CA",
+            "Test PCK",
+            &pck_key,
+            &root_key,
+        )) + &pem(&root_certificate());
+
+        let mut header = vec![0u8; HEADER_LEN];
+        header[0..2].copy_from_slice(&version.to_le_bytes());
+        header[2..4].copy_from_slice(&ATT_KEY_TYPE_ECDSA_P256.to_le_bytes());
+        header[4..8].copy_from_slice(&tee_type.to_le_bytes());
+        let signed = [header, body].concat();
+        let quote_signature: Signature = attestation_key.sign(&signed);
+
+        let attestation_public = public_key(&attestation_key)[1..].to_vec();
+        let qe_auth = vec![7u8; 32];
+        let mut qe_report = vec![0u8; SGX_REPORT_LEN];
+        qe_report[SGX_REPORT_DATA..SGX_REPORT_DATA + 32].copy_from_slice(&env::sha256(
+            &[&attestation_public[..], &qe_auth[..]].concat(),
+        ));
+        let qe_signature: Signature = pck_key.sign(&qe_report);
+
+        let qe_data = [
+            qe_report,
+            qe_signature.to_bytes().to_vec(),
+            (qe_auth.len() as u16).to_le_bytes().to_vec(),
+            qe_auth,
+            CERT_DATA_PCK_CHAIN.to_le_bytes().to_vec(),
+            (chain.len() as u32).to_le_bytes().to_vec(),
+            chain.into_bytes(),
+        ]
+        .concat();
+        // v4 wraps the QE report in a certification data entry
+        let qe_data = if version == 4 {
+            [
+                CERT_DATA_QE_REPORT.to_le_bytes().to_vec(),
+                (qe_data.len() as u32).to_le_bytes().to_vec(),
+                qe_data,
+            ]
+            .concat()
+        } else {
+            qe_data
+        };
+        let signature_data = [
+            quote_signature.to_bytes().to_vec(),
+            attestation_public,
+            qe_data,
+        ]
+        .concat();
+
+        [
+            signed,
+            (signature_data.len() as u32).to_le_bytes().to_vec(),
+            signature_data,
+        ]
+        .concat()
+    }
+}
@@ -0,0 +1,402 @@
+use crate::*;
+use near_sdk::{ext_contract, PromiseError};
+
+pub const MAX_ORACLE_CALL_ASSETS: usize = 20;
+pub const MAX_PRICE_UPDATE_SUBSCRIPTIONS: usize = 50;
+pub const MAX_CALLBACK_GAS: Gas = Gas::from_tgas(50);
+pub const DEFAULT_CALLBACK_GAS: Gas = Gas::from_tgas(10);
+// Left for the rest of the reporting call after callbacks are scheduled
+const CALLBACK_GAS_RESERVE: Gas = Gas::from_tgas(10);
+// Callback gas one reporting call attaches to deliveries in total
+pub const MAX_DELIVERY_GAS: Gas = Gas::from_tgas(100);
+// yoctoNEAR per gas unit charged for callback gas, the protocol's minimum
+// gas price
+const CALLBACK_GAS_PRICE: u128 = 100_000_000;
+// Deliveries a subscriber's balance must cover when it subscribes
+pub const MIN_PREPAID_DELIVERIES: u128 = 10;
+// Storage held for a price update balance entry and for one subscription
+const BALANCE_STORAGE_BYTES: u128 = 200;
+const SUBSCRIPTION_STORAGE_BYTES: u128 = 200;
+
+/// Prices delivered to a consumer contract by `oracle_call`.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceBundle {
+    pub timestamp: u64,
+    pub recency_threshold: u64,
+    /// One entry per requested asset, in request order.
+    pub prices: Vec<AssetPrice>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetPrice {
+    pub asset_id: String,
+    /// `None` when `get_price` would return nothing for the asset.
+    pub data: Option<PriceData>,
+}
+
+/// A consumer contract's request to be called back when an asset's price
+/// moves or goes without an update for too long.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceUpdateSubscription {
+    pub receiver_id: AccountId,
+    /// Move from the last delivered price that triggers a callback, in basis
+    /// points. Zero disables the trigger.
+    pub deviation_bps: u16,
+    /// Longest time between callbacks, in nanoseconds. Checked when the asset
+    /// is aggregated. Zero disables the trigger.
+    pub heartbeat: u64,
+    pub callback_gas: Gas,
+    pub last_delivered: Option<U128>,
+    pub last_delivered_at: u64,
+    /// Taken from the subscriber's balance for the subscription's storage and
+    /// returned to it when the subscription is dropped.
+    pub storage_deposit: NearToken,
+}
+
+impl PriceUpdateSubscription {
+    fn triggered(&self, multiplier: u128, now: u64) -> bool {
+        let Some(last) = self.last_delivered else {
+            return true;
+        };
+        (self.deviation_bps > 0 && deviation_bps(multiplier, last.0) >= self.deviation_bps as u128)
+            || (self.heartbeat > 0 && now.saturating_sub(self.last_delivered_at) >= self.heartbeat)
+    }
+}
+
+/// Interface consumer contracts implement to receive prices.
+#[ext_contract(ext_oracle_receiver)]
+pub trait OracleReceiver {
+    fn oracle_on_call(&mut self, sender_id: AccountId, data: PriceBundle, msg: String);
+    fn oracle_on_price_update(&mut self, data: PriceData);
+}
+
+#[near_bindgen]
+impl Oracle {
+    // Calls `oracle_on_call` on `receiver_id` with the current prices of
+    // `asset_ids`, so the receiver can act on them in the same transaction.
+    // Charged like `pull_price` per asset that has a price; the rest of the
+    // deposit is forwarded to the receiver along with all remaining gas, and
+    // returned to the caller if the receiver fails.
+    #[payable]
+    pub fn oracle_call(
+        &mut self,
+        receiver_id: AccountId,
+        asset_ids: Vec<String>,
+        msg: String,
+    ) -> Promise {
+        assert!(!asset_ids.is_empty(), "No assets requested");
+        assert!(
+            asset_ids.len() <= MAX_ORACLE_CALL_ASSETS,
+            "Too many assets requested"
+        );
+        let data = self.price_bundle(asset_ids);
+        let priced = data.prices.iter().filter(|p| p.data.is_some()).count();
+        let forwarded = self.charge_price_fee(priced);
+        let sender_id = env::predecessor_account_id();
+
+        ext_oracle_receiver::ext(receiver_id)
+            .with_attached_deposit(forwarded)
+            .with_unused_gas_weight(1)
+            .oracle_on_call(sender_id.clone(), data, msg)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(Gas::from_tgas(10))
+                    .on_oracle_call(sender_id, forwarded),
+            )
+    }
+
+    // Returns the forwarded deposit to the caller when the receiver failed.
+    // Whatever the receiver returns on success is ignored.
+    #[private]
+    pub fn on_oracle_call(&mut self, sender_id: AccountId, amount: NearToken) {
+        if !near_sdk::is_promise_success() && !amount.is_zero() {
+            Promise::new(sender_id).transfer(amount);
+        }
+    }
+
+    // Registers the caller for `oracle_on_price_update` callbacks on the asset,
+    // replacing any subscription it already has there. The caller's balance
+    // must hold the storage deposit and `MIN_PREPAID_DELIVERIES` deliveries.
+    // When the asset is full, a subscription whose balance can't pay for its
+    // next delivery gives up its slot.
+    pub fn subscribe_price_updates(
+        &mut self,
+        asset_id: String,
+        deviation_bps: u16,
+        heartbeat: u64,
+        callback_gas: Option<Gas>,
+    ) {
+        assert!(self.assets.get(&asset_id).is_some(), "Asset not found");
+        assert!(
+            deviation_bps > 0 || heartbeat > 0,
+            "Set a deviation or heartbeat trigger"
+        );
+        let callback_gas = callback_gas.unwrap_or(DEFAULT_CALLBACK_GAS);
+        assert!(
+            callback_gas <= MAX_CALLBACK_GAS,
+            "Callback gas above the limit"
+        );
+        let receiver_id = env::predecessor_account_id();
+
+        let mut subscriptions = self.price_subscriptions.get(&asset_id).unwrap_or_default();
+        if let Some(index) = subscriptions
+            .iter()
+            .position(|s| s.receiver_id == receiver_id)
+        {
+            let replaced = subscriptions.remove(index);
+            self.credit_price_update_balance(&receiver_id, replaced.storage_deposit);
+        }
+        let storage_deposit = storage_cost(SUBSCRIPTION_STORAGE_BYTES);
+        let required = self
+            .delivery_cost(callback_gas)
+            .saturating_mul(MIN_PREPAID_DELIVERIES)
+            .saturating_add(storage_deposit);
+        let balance = self.get_price_update_balance(receiver_id.clone());
+        assert!(
+            balance >= required,
+            "Price update balance must cover storage and {} deliveries",
+            MIN_PREPAID_DELIVERIES
+        );
+
+        if subscriptions.len() >= MAX_PRICE_UPDATE_SUBSCRIPTIONS {
+            let index = subscriptions
+                .iter()
+                .position(|s| {
+                    self.get_price_update_balance(s.receiver_id.clone())
+                        < self.delivery_cost(s.callback_gas)
+                })
+                .expect("Too many subscriptions for asset");
+            let evicted = subscriptions.remove(index);
+            self.credit_price_update_balance(&evicted.receiver_id, evicted.storage_deposit);
+            OracleEvent::PriceUpdatesUnsubscribed {
+                receiver_id: evicted.receiver_id,
+                asset_id: asset_id.clone(),
+            }
+            .emit();
+        }
+        self.price_update_balances
+            .insert(&receiver_id, &balance.saturating_sub(storage_deposit));
+        subscriptions.push(PriceUpdateSubscription {
+            receiver_id: receiver_id.clone(),
+            deviation_bps,
+            heartbeat,
+            callback_gas,
+            last_delivered: None,
+            last_delivered_at: 0,
+            storage_deposit,
+        });
+        self.price_subscriptions.insert(&asset_id, &subscriptions);
+        OracleEvent::PriceUpdatesSubscribed {
+            receiver_id,
+            asset_id,
+            deviation_bps,
+            heartbeat,
+        }
+        .emit();
+    }
+
+    pub fn unsubscribe_price_updates(&mut self, asset_id: String) {
+        let receiver_id = env::predecessor_account_id();
+        let mut subscriptions = self.price_subscriptions.get(&asset_id).unwrap_or_default();
+        let index = subscriptions
+            .iter()
+            .position(|s| s.receiver_id == receiver_id)
+            .expect("Not subscribed");
+        let removed = subscriptions.remove(index);
+        self.credit_price_update_balance(&receiver_id, removed.storage_deposit);
+        if subscriptions.is_empty() {
+            self.price_subscriptions.remove(&asset_id);
+        } else {
+            self.price_subscriptions.insert(&asset_id, &subscriptions);
+        }
+        OracleEvent::PriceUpdatesUnsubscribed {
+            receiver_id,
+            asset_id,
+        }
+        .emit();
+    }
+
+    // Tops up the balance that pays for the caller's (or `receiver_id`'s)
+    // price update callbacks. The first deposit also pays for the balance's
+    // storage, which is kept.
+    #[payable]
+    pub fn fund_price_updates(&mut self, receiver_id: Option<AccountId>) -> NearToken {
+        let mut amount = env::attached_deposit();
+        let receiver_id = receiver_id.unwrap_or_else(env::predecessor_account_id);
+        if self.price_update_balances.get(&receiver_id).is_none() {
+            let storage = storage_cost(BALANCE_STORAGE_BYTES);
+            assert!(
+                amount > storage,
+                "Deposit must exceed the storage cost of {}",
+                storage
+            );
+            amount = amount.saturating_sub(storage);
+        }
+        assert!(!amount.is_zero(), "Attach a deposit to fund price updates");
+        let balance = self
+            .get_price_update_balance(receiver_id.clone())
+            .saturating_add(amount);
+        self.price_update_balances.insert(&receiver_id, &balance);
+        balance
+    }
+
+    pub fn withdraw_price_update_balance(&mut self, amount: NearToken) -> Promise {
+        let receiver_id = env::predecessor_account_id();
+        let balance = self.get_price_update_balance(receiver_id.clone());
+        assert!(!amount.is_zero(), "Amount must be positive");
+        assert!(amount <= balance, "Amount exceeds balance");
+        self.price_update_balances
+            .insert(&receiver_id, &balance.saturating_sub(amount));
+        Promise::new(receiver_id.clone()).transfer(amount).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(Gas::from_tgas(10))
+                .on_price_update_balance_withdrawn(receiver_id, amount),
+        )
+    }
+
+    // Restores the balance when the transfer failed
+    #[private]
+    pub fn on_price_update_balance_withdrawn(
+        &mut self,
+        receiver_id: AccountId,
+        amount: NearToken,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) {
+        if result.is_err() {
+            self.credit_price_update_balance(&receiver_id, amount);
+        }
+    }
+
+    pub fn get_price_update_subscriptions(&self, asset_id: String) -> Vec<PriceUpdateSubscription> {
+        self.price_subscriptions.get(&asset_id).unwrap_or_default()
+    }
+
+    pub fn get_price_update_balance(&self, account_id: AccountId) -> NearToken {
+        self.price_update_balances
+            .get(&account_id)
+            .unwrap_or_default()
+    }
+}
+
+impl Oracle {
+    // Schedules `oracle_on_price_update` for every subscription whose
+    // trigger fired on an aggregate produced during this call. Runs once the
+    // reporting is done, so callbacks can't starve later items of a batch,
+    // and attaches at most `MAX_DELIVERY_GAS` in total, serving the longest
+    // waiting subscribers first. A delivery that the subscriber's balance
+    // can't pay for, or that doesn't fit, stays pending until the next
+    // aggregate. Each delivery charges the notification fee plus its callback
+    // gas, so the reporting node's gas is paid back through the reward pool.
+    pub(crate) fn deliver_price_updates(&mut self) {
+        let now = env::block_timestamp();
+        let mut feeds = Vec::new();
+        let mut queue = Vec::new();
+        for asset_id in std::mem::take(&mut self.updated_assets) {
+            let Some(subscriptions) = self.price_subscriptions.get(&asset_id) else {
+                continue;
+            };
+            let Some(data) = self.get_price(asset_id.clone()) else {
+                continue;
+            };
+            for (index, subscription) in subscriptions.iter().enumerate() {
+                if subscription.triggered(data.price.multiplier, now) {
+                    queue.push((subscription.last_delivered_at, feeds.len(), index));
+                }
+            }
+            feeds.push((asset_id, subscriptions, data));
+        }
+        queue.sort();
+
+        let mut budget = MAX_DELIVERY_GAS;
+        for (_, feed, index) in queue {
+            let (asset_id, subscriptions, data) = &mut feeds[feed];
+            let subscription = &mut subscriptions[index];
+            let fee = self.delivery_cost(subscription.callback_gas);
+            let receiver_id = subscription.receiver_id.clone();
+            let balance = self.get_price_update_balance(receiver_id.clone());
+            let remaining_gas = env::prepaid_gas().saturating_sub(env::used_gas());
+            if balance < fee
+                || subscription.callback_gas > budget
+                || remaining_gas
+                    < subscription
+                        .callback_gas
+                        .saturating_add(CALLBACK_GAS_RESERVE)
+            {
+                continue;
+            }
+            budget = budget.saturating_sub(subscription.callback_gas);
+            self.price_update_balances
+                .insert(&receiver_id, &balance.saturating_sub(fee));
+            self.reward_pool = self.reward_pool.saturating_add(fee);
+            subscription.last_delivered = Some(U128(data.price.multiplier));
+            subscription.last_delivered_at = now;
+
+            ext_oracle_receiver::ext(receiver_id.clone())
+                .with_static_gas(subscription.callback_gas)
+                .with_unused_gas_weight(0)
+                .oracle_on_price_update(data.clone());
+            OracleEvent::PriceUpdateDelivered {
+                receiver_id,
+                asset_id: asset_id.clone(),
+                round_id: data.round_id,
+            }
+            .emit();
+        }
+        for (asset_id, subscriptions, _) in feeds {
+            self.price_subscriptions.insert(&asset_id, &subscriptions);
+        }
+    }
+
+    // Drops the asset's subscriptions, returning their storage deposits to
+    // the subscribers' balances
+    pub(crate) fn drop_price_subscriptions(&mut self, asset_id: &String) {
+        let subscriptions = self
+            .price_subscriptions
+            .remove(asset_id)
+            .unwrap_or_default();
+        for subscription in subscriptions {
+            self.credit_price_update_balance(
+                &subscription.receiver_id,
+                subscription.storage_deposit,
+            );
+            OracleEvent::PriceUpdatesUnsubscribed {
+                receiver_id: subscription.receiver_id,
+                asset_id: asset_id.clone(),
+            }
+            .emit();
+        }
+    }
+
+    // What one delivery with `callback_gas` costs the subscriber
+    fn delivery_cost(&self, callback_gas: Gas) -> NearToken {
+        NearToken::from_yoctonear(callback_gas.as_gas() as u128 * CALLBACK_GAS_PRICE)
+            .saturating_add(self.fee_policy.notification_fee)
+    }
+
+    fn credit_price_update_balance(&mut self, receiver_id: &AccountId, amount: NearToken) {
+        let balance = self.get_price_update_balance(receiver_id.clone());
+        self.price_update_balances
+            .insert(receiver_id, &balance.saturating_add(amount));
+    }
+
+    fn price_bundle(&self, asset_ids: Vec<String>) -> PriceBundle {
+        PriceBundle {
+            timestamp: env::block_timestamp(),
+            recency_threshold: self.recency_threshold,
+            prices: asset_ids
+                .into_iter()
+                .map(|asset_id| AssetPrice {
+                    data: self.get_price(asset_id.clone()),
+                    asset_id,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn storage_cost(bytes: u128) -> NearToken {
+    env::storage_byte_cost().saturating_mul(bytes)
+}
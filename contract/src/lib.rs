@@ -1,9 +1,25 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8, U128};
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{env, near_bindgen, AccountId, BorshStorageKey, PanicOnDefault};
+use near_sdk::{
+    env, near_bindgen, AccountId, BorshStorageKey, CryptoHash, CurveType, Gas, GasWeight,
+    NearToken, PanicOnDefault, Promise, PublicKey,
+};
 use std::collections::HashSet;
 
+mod attestation;
+mod delivery;
+mod migration;
+mod rewards;
+mod staking;
+
+pub use attestation::{AttestationCollateral, EnclavePolicy, SignerPolicy};
+pub use delivery::{AssetPrice, OracleReceiver, PriceBundle, PriceUpdateSubscription};
+pub use migration::STATE_VERSION;
+pub use rewards::{FeePolicy, RewardDistribution};
+pub use staking::{OperatorBond, StakingPolicy};
+
 #[derive(BorshSerialize, BorshStorageKey)]
 pub enum StorageKey {
     Assets,
@@ -15,11 +31,27 @@ pub enum StorageKey {
     WhitelistedOperators,
     ApprovedCodeHashes,
     ApprovedEnclaves,
-    OperatorToNode,
+    OperatorNodes,
     NodeToOperator,
     AdminProposers,
     AdminVoters,
     AdminProposals,
+    TwapSamples,
+    PriceHistory,
+    HistoryCursors,
+    PriceAccumulators,
+    OpenRounds,
+    RoundCounters,
+    ApprovedRootCerts,
+    PendingRotations,
+    AssetNodes,
+    Bonds,
+    Subscriptions,
+    RewardPoints,
+    Rewards,
+    PriceSubscriptions,
+    PriceUpdateBalances,
+    DistributingPoints,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
@@ -28,6 +60,14 @@ pub struct Price {
     pub multiplier: u128,
     pub decimals: u8,
     pub timestamp: u64,
+    /// Median absolute deviation of the reports behind an aggregate, in the
+    /// same units as `multiplier`. Always zero on individual reports.
+    #[serde(default)]
+    pub conf: u128,
+    /// Round that produced this price, or that a report was submitted into.
+    /// Zero for derived prices such as TWAP and EMA.
+    #[serde(default)]
+    pub round_id: u64,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
@@ -36,6 +76,64 @@ pub struct PriceReport {
     pub oracle_id: AccountId,
     pub price: Price,
     pub timestamp: u64,
+    /// Set when the report falls outside the asset's deviation band and is
+    /// excluded from aggregation.
+    #[serde(default)]
+    pub flagged: bool,
+}
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default, PartialEq, Debug,
+)]
+#[serde(crate = "near_sdk::serde", tag = "type", content = "detail")]
+pub enum AggregationMethod {
+    #[default]
+    Median,
+    /// Mean after dropping `trim_bps` of the reports from each tail.
+    TrimmedMean { trim_bps: u16 },
+    /// Median where each report counts with its node's weight.
+    WeightedMedian,
+    /// Time-weighted average of the spot median over the last `window` ns.
+    Twap { window: u64 },
+    /// Weighted median where the node's weight is scaled by its reputation
+    /// score, so established accurate nodes outweigh newcomers.
+    ReputationWeightedMedian,
+    /// Weighted median where the node's weight is scaled by its operator's bond.
+    StakeWeightedMedian,
+}
+
+/// Lifecycle of a listed asset.
+///
+/// | Status          | Reports accepted | Price views |
+/// |-----------------|------------------|-------------|
+/// | `Active`        | yes              | yes         |
+/// | `ReportingOnly` | yes              | `None`      |
+/// | `QueryFrozen`   | no               | `None`      |
+/// | `Delisted`      | no               | `None`      |
+///
+/// `QueryFrozen` is a temporary halt that can be lifted; `Delisted` is final.
+/// Price history stays readable through `get_price_at` and `get_price_history`
+/// in every status.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default, PartialEq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AssetStatus {
+    #[default]
+    Active,
+    ReportingOnly,
+    QueryFrozen,
+    Delisted,
+}
+
+impl AssetStatus {
+    pub fn accepts_reports(&self) -> bool {
+        matches!(self, AssetStatus::Active | AssetStatus::ReportingOnly)
+    }
+
+    pub fn serves_queries(&self) -> bool {
+        matches!(self, AssetStatus::Active)
+    }
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
@@ -45,8 +143,19 @@ pub struct Asset {
     pub symbol: String,
     pub name: String,
     pub decimals: u8,
-    pub active: bool,
+    #[serde(default)]
+    pub status: AssetStatus,
     pub min_sources: u8,
+    /// Maximum distance from the provisional median, in basis points, for a
+    /// report to be aggregated. Zero disables outlier rejection.
+    #[serde(default)]
+    pub max_deviation_bps: u16,
+    #[serde(default)]
+    pub aggregation: AggregationMethod,
+    /// Accepted reports, one per operator, that close a round before its
+    /// deadline. Zero closes it as soon as the required sources are in.
+    #[serde(default)]
+    pub round_quorum: u8,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
@@ -57,15 +166,233 @@ pub struct OracleNode {
     pub registered_at: u64,
     pub code_hash: String,
     pub last_report: u64,
+    /// False while the node is suspended; its reports are then rejected and
+    /// left out of aggregation.
     pub active: bool,
+    pub outlier_reports: u64,
+    pub weight: u32,
+    /// End of the current suspension, `u64::MAX` when it lasts until the node
+    /// is reinstated by governance. Zero when not suspended.
+    pub suspended_until: u64,
+    pub consecutive_outliers: u32,
+    /// Enclave-generated ed25519 key that signs `report_price_signed` payloads.
+    pub signing_key: Option<PublicKey>,
+    /// Highest nonce accepted from `report_price_signed`.
+    pub last_nonce: u64,
+    /// Issue time of the quote the node last registered with.
+    pub attested_at: u64,
+    /// MRENCLAVE or MRTD (hex) of that quote.
+    pub measurement: String,
+    pub reputation: NodeReputation,
+}
+
+/// Rounds a new node needs before its reputation score can reach its full value.
+pub const REPUTATION_WARMUP_ROUNDS: u64 = 100;
+
+/// Largest `history_depth` the owner can configure.
+pub const MAX_HISTORY_DEPTH: u32 = 1_000;
+
+/// Entries past `history_depth` dropped per history update, so lowering the
+/// depth shrinks each asset's history over a few updates instead of one call.
+const HISTORY_TRIM_PER_UPDATE: u64 = 2;
+
+/// Track record built from the rounds of the assets a node may report.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NodeReputation {
+    /// Closed rounds the node was eligible for while active.
+    pub rounds_eligible: u64,
+    /// Of those, rounds it reported in.
+    pub rounds_reported: u64,
+    /// Moving average of the deviation from the round's aggregate, in bps.
+    pub avg_deviation_bps: u32,
+    /// Moving average of the delay between round opening and the report, in ns.
+    pub avg_latency: u64,
+    /// 0-10000: uptime, accuracy (counted twice) and timeliness averaged,
+    /// then scaled down until the node has `REPUTATION_WARMUP_ROUNDS` rounds.
+    pub score: u16,
+}
+
+impl NodeReputation {
+    // Averages move 1/8 of the way towards each new sample
+    fn record(&mut self, latency: Option<u64>, deviation_bps: Option<u32>, round_duration: u64) {
+        self.rounds_eligible += 1;
+        if let Some(latency) = latency {
+            self.rounds_reported += 1;
+            self.avg_latency = if self.rounds_reported == 1 {
+                latency
+            } else {
+                self.avg_latency - self.avg_latency / 8 + latency / 8
+            };
+        }
+        if let Some(deviation) = deviation_bps {
+            self.avg_deviation_bps = if self.rounds_reported == 1 {
+                deviation
+            } else {
+                self.avg_deviation_bps - self.avg_deviation_bps / 8 + deviation / 8
+            };
+        }
+
+        let uptime = self.rounds_reported * 10_000 / self.rounds_eligible;
+        let accuracy = 10_000 - (self.avg_deviation_bps as u64).min(10_000);
+        let timeliness = match round_duration {
+            0 => 10_000,
+            duration => {
+                10_000 - ((self.avg_latency as u128 * 10_000 / duration as u128) as u64).min(10_000)
+            }
+        };
+        let blended = (uptime + 2 * accuracy + timeliness) / 4;
+        let maturity = self.rounds_eligible.min(REPUTATION_WARMUP_ROUNDS);
+        self.score = (blended * maturity / REPUTATION_WARMUP_ROUNDS) as u16;
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NodeAttestationView {
+    pub code_hash: String,
+    pub attested_at: u64,
+    /// Zero when re-attestation is not required.
+    pub expires_at: u64,
+    pub lapsed: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetCoverage {
+    pub asset_id: String,
+    /// False when any authorized node may report the asset.
+    pub assigned: bool,
+    /// Authorized nodes allowed to report the asset.
+    pub eligible_nodes: Vec<AccountId>,
+    /// Eligible nodes whose latest report is within `recency_threshold`.
+    pub reporting_nodes: Vec<AccountId>,
+    pub required_sources: u8,
+}
+
+/// Replacement staged by an operator. `new_node` may register while
+/// `old_node` keeps reporting; its registration retires `old_node`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NodeRotation {
+    pub old_node: AccountId,
+    pub new_node: AccountId,
+    pub staged_at: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JailPolicy {
+    /// Length of automatic suspensions, in nanoseconds.
+    pub jail_duration: u64,
+    /// Consecutive flagged reports after which a node is suspended. Zero disables.
+    pub max_consecutive_outliers: u32,
+    /// Time without a report after which anyone may suspend a node through
+    /// `jail_idle_node`. Zero disables.
+    pub heartbeat_timeout: u64,
+}
+
+impl Default for JailPolicy {
+    fn default() -> Self {
+        Self {
+            jail_duration: 3_600_000_000_000,
+            max_consecutive_outliers: 5,
+            heartbeat_timeout: 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum SuspensionReason {
+    Governance,
+    MissedHeartbeat,
+    RepeatedOutliers,
+}
+
+// Sequence range [oldest, next) of an asset's stored price history
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct HistoryCursor {
+    pub oldest: u64,
+    pub next: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct HistoryEntry {
+    pub price: Price,
+    // Price accumulator value at `price.timestamp`, before this price took effect
+    pub cumulative: u128,
+}
+
+// Running time integral of the aggregated price plus its EMA. The last
+// aggregate is treated as in force until the next one is published.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceAccumulator {
+    pub cumulative: u128,
+    pub last_price: u128,
+    pub last_conf: u128,
+    pub last_timestamp: u64,
+    pub decimals: u8,
+    pub ema_price: u128,
+    pub ema_conf: u128,
+}
+
+#[derive(BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceInput {
+    pub asset_id: String,
+    pub multiplier: u128,
+    pub decimals: u8,
+}
+
+/// Price batch signed inside the enclave with the node's signing key. The
+/// signature covers the Borsh encoding of this struct, so any account can
+/// relay it.
+#[derive(BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SignedPriceReport {
+    /// The oracle contract account, so a payload cannot be replayed elsewhere.
+    pub contract_id: AccountId,
+    pub node_account: AccountId,
+    /// Must exceed the node's `last_nonce`.
+    pub nonce: u64,
+    pub signed_at: u64,
+    pub prices: Vec<PriceInput>,
 }
 
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
+pub struct RejectedPrice {
+    pub asset_id: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BatchReportResult {
+    pub accepted: Vec<String>,
+    pub rejected: Vec<RejectedPrice>,
+}
+
+// Reporting round for one asset. It closes once every authorized node has
+// reported, or once its deadline passes.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceRound {
+    pub id: u64,
+    pub opened_at: u64,
+    pub deadline: u64,
+    pub reports: Vec<PriceReport>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
 pub struct PriceData {
     pub asset_id: String,
     pub price: Price,
     pub num_sources: u8,
+    pub round_id: u64,
 }
 
 // Pyth-compatible price structure
@@ -78,6 +405,17 @@ pub struct PythPrice {
     pub publish_time: i64,
 }
 
+impl From<&Price> for PythPrice {
+    fn from(price: &Price) -> Self {
+        Self {
+            price: price.multiplier as i64,
+            conf: u64::try_from(price.conf).unwrap_or(u64::MAX),
+            expo: -(price.decimals as i32),
+            publish_time: (price.timestamp / 1_000_000_000) as i64,
+        }
+    }
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde", tag = "type", content = "detail")]
 pub enum AdminAction {
@@ -100,11 +438,103 @@ pub enum AdminAction {
     RemoveAttestation {
         code_hash: String,
     },
+    RemoveEnclaveMeasurement {
+        code_hash: String,
+        mr_enclave: String,
+    },
+    SetSignerPolicy {
+        code_hash: String,
+        policy: SignerPolicy,
+    },
     Pause,
     Resume,
     UpdateConfig {
         recency_threshold: Option<u64>,
         min_report_count: Option<u8>,
+        history_depth: Option<u32>,
+        ema_period: Option<u64>,
+        round_duration: Option<u64>,
+    },
+    AddAsset {
+        asset: Asset,
+    },
+    UpdateAsset {
+        asset: Asset,
+    },
+    /// Same as `SetAssetStatus` with `QueryFrozen`.
+    DeactivateAsset {
+        asset_id: String,
+    },
+    RemoveAsset {
+        asset_id: String,
+    },
+    SetAssetStatus {
+        asset_id: String,
+        status: AssetStatus,
+    },
+    SetAggregationMethod {
+        asset_id: String,
+        method: AggregationMethod,
+    },
+    /// Restricts reporting of the asset to these nodes. Empty lets every
+    /// authorized node report it.
+    SetAssetNodes {
+        asset_id: String,
+        node_accounts: Vec<AccountId>,
+    },
+    SetNodeWeight {
+        account_id: AccountId,
+        weight: u32,
+    },
+    /// Suspends a node for `duration` ns, or until reinstated when `None`.
+    SuspendNode {
+        account_id: AccountId,
+        duration: Option<u64>,
+    },
+    ReinstateNode {
+        account_id: AccountId,
+    },
+    SetJailPolicy {
+        policy: JailPolicy,
+    },
+    /// Nodes must re-register with a fresh quote within `window` ns of their
+    /// last attestation. Zero disables expiry.
+    SetReattestationWindow {
+        window: u64,
+    },
+    SetMaxNodesPerOperator {
+        max_nodes: u32,
+    },
+    /// Trusts a DER root certificate for quote verification.
+    ApproveRootCertificate {
+        cert: Base64VecU8,
+    },
+    RemoveRootCertificate {
+        cert_hash: Base58CryptoHash,
+    },
+    SetAttestationCollateral {
+        collateral: AttestationCollateral,
+    },
+    /// Sends `amount` of the operator's bond to the treasury.
+    SlashOperator {
+        operator_id: AccountId,
+        amount: NearToken,
+        reason: String,
+    },
+    SetStakingPolicy {
+        policy: StakingPolicy,
+    },
+    SetTreasury {
+        account_id: AccountId,
+    },
+    SetFeePolicy {
+        policy: FeePolicy,
+    },
+    /// Deploys new contract code on this account and calls `migrate`.
+    /// The code is omitted from JSON output; see `get_upgrade_code_hash`.
+    UpgradeContract {
+        #[serde(skip_serializing)]
+        code: Base64VecU8,
     },
 }
 
@@ -130,9 +560,292 @@ pub struct AdminRoleView {
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
+/// DCAP quote (SGX or TDX) from the node's enclave. Its report data must be
+/// `sha256(signer public key) || issued_at (u64 little-endian)` followed by
+/// zero padding, where the signer key is the one signing `register_node`.
 pub struct AttestationData {
-    pub mr_enclave: String,
-    pub issued_at: u64,
+    pub quote: Base64VecU8,
+}
+
+pub const EVENT_STANDARD: &str = "tee-oracle";
+pub const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+// NEP-297 events, logged as `EVENT_JSON:{"standard":..,"version":..,"event":..,"data":..}`
+#[derive(Serialize)]
+#[serde(
+    crate = "near_sdk::serde",
+    tag = "event",
+    content = "data",
+    rename_all = "snake_case"
+)]
+pub enum OracleEvent {
+    PriceReported {
+        asset_id: String,
+        node_account: AccountId,
+        multiplier: U128,
+        decimals: u8,
+    },
+    PriceUpdated {
+        asset_id: String,
+        round_id: u64,
+        multiplier: U128,
+        decimals: u8,
+        conf: U128,
+        timestamp: u64,
+        num_sources: u8,
+    },
+    PriceUnavailable {
+        asset_id: String,
+        fresh_reports: u32,
+        required_sources: u32,
+    },
+    RoundOpened {
+        asset_id: String,
+        round_id: u64,
+        deadline: u64,
+    },
+    RoundExpired {
+        asset_id: String,
+        round_id: u64,
+        reports: u32,
+    },
+    OutlierFlagged {
+        asset_id: String,
+        node_account: AccountId,
+        multiplier: U128,
+    },
+    AssetAdded {
+        asset_id: String,
+    },
+    AssetUpdated {
+        asset_id: String,
+    },
+    AssetStatusChanged {
+        asset_id: String,
+        status: AssetStatus,
+    },
+    AssetRemoved {
+        asset_id: String,
+    },
+    AggregationMethodUpdated {
+        asset_id: String,
+        method: AggregationMethod,
+    },
+    AssetNodesSet {
+        asset_id: String,
+        node_accounts: Vec<AccountId>,
+    },
+    NodeOperatorAdded {
+        operator_id: AccountId,
+    },
+    NodeOperatorRemoved {
+        operator_id: AccountId,
+    },
+    NodeAccountSet {
+        operator_id: AccountId,
+        node_account: AccountId,
+    },
+    NodeRegistered {
+        node_account: AccountId,
+        operator_id: AccountId,
+        code_hash: String,
+        signing_key: Option<PublicKey>,
+    },
+    NodeRemoved {
+        node_account: AccountId,
+        operator_id: AccountId,
+    },
+    /// Nodes cut off by revoking their code hash or enclave measurement.
+    NodesDeauthorized {
+        code_hash: String,
+        mr_enclave: Option<String>,
+        node_accounts: Vec<AccountId>,
+    },
+    NodeWeightSet {
+        node_account: AccountId,
+        weight: u32,
+    },
+    NodeSuspended {
+        node_account: AccountId,
+        until: u64,
+        reason: SuspensionReason,
+    },
+    NodeReinstated {
+        node_account: AccountId,
+    },
+    JailPolicySet {
+        jail_duration: u64,
+        max_consecutive_outliers: u32,
+        heartbeat_timeout: u64,
+    },
+    CodeHashApproved {
+        code_hash: String,
+    },
+    CodeHashRemoved {
+        code_hash: String,
+    },
+    AttestationApproved {
+        code_hash: String,
+        mr_enclave: String,
+    },
+    AttestationRemoved {
+        code_hash: String,
+    },
+    EnclaveMeasurementRemoved {
+        code_hash: String,
+        mr_enclave: String,
+    },
+    SignerPolicySet {
+        code_hash: String,
+        policy: SignerPolicy,
+    },
+    ReattestationWindowSet {
+        window: u64,
+    },
+    MaxNodesPerOperatorSet {
+        max_nodes: u32,
+    },
+    NodeRotationStaged {
+        operator_id: AccountId,
+        old_node: AccountId,
+        new_node: AccountId,
+    },
+    NodeRotated {
+        operator_id: AccountId,
+        old_node: AccountId,
+        new_node: AccountId,
+    },
+    BondDeposited {
+        operator_id: AccountId,
+        amount: NearToken,
+        bonded: NearToken,
+    },
+    BondUnbonding {
+        operator_id: AccountId,
+        amount: NearToken,
+        withdrawable_at: u64,
+    },
+    BondWithdrawn {
+        operator_id: AccountId,
+        amount: NearToken,
+    },
+    OperatorSlashed {
+        operator_id: AccountId,
+        amount: NearToken,
+        treasury: AccountId,
+        reason: String,
+    },
+    StakingPolicySet {
+        policy: StakingPolicy,
+    },
+    TreasurySet {
+        account_id: AccountId,
+    },
+    ConsumerSubscribed {
+        consumer_id: AccountId,
+        amount: NearToken,
+        paid_until: u64,
+    },
+    FeePolicySet {
+        policy: FeePolicy,
+    },
+    RewardsDistributed {
+        amount: NearToken,
+        total_points: u64,
+    },
+    RewardsClaimed {
+        operator_id: AccountId,
+        amount: NearToken,
+    },
+    PriceUpdatesSubscribed {
+        receiver_id: AccountId,
+        asset_id: String,
+        deviation_bps: u16,
+        heartbeat: u64,
+    },
+    PriceUpdatesUnsubscribed {
+        receiver_id: AccountId,
+        asset_id: String,
+    },
+    PriceUpdateDelivered {
+        receiver_id: AccountId,
+        asset_id: String,
+        round_id: u64,
+    },
+    AttestationMaxAgeSet {
+        max_age: u64,
+    },
+    RootCertificateApproved {
+        cert_hash: Base58CryptoHash,
+    },
+    RootCertificateRemoved {
+        cert_hash: Base58CryptoHash,
+    },
+    AttestationCollateralSet {
+        collateral: AttestationCollateral,
+    },
+    ConfigUpdated {
+        recency_threshold: u64,
+        min_report_count: u8,
+        history_depth: u32,
+        ema_period: u64,
+        round_duration: u64,
+    },
+    Paused,
+    Resumed,
+    AdminRoleConfigured {
+        proposers: Vec<AccountId>,
+        voters: Vec<AccountId>,
+        timelock_delay: u64,
+        quorum_bps: u16,
+    },
+    ProposalCreated {
+        proposal_id: u64,
+        proposer: AccountId,
+        action: AdminAction,
+        scheduled_for: u64,
+    },
+    ProposalApproved {
+        proposal_id: u64,
+        voter: AccountId,
+    },
+    ProposalExecuted {
+        proposal_id: u64,
+        executor: AccountId,
+    },
+    ProposalCancelled {
+        proposal_id: u64,
+    },
+    ContractUpgradeScheduled {
+        code_hash: Base58CryptoHash,
+    },
+    StateMigrated {
+        from_version: u16,
+        to_version: u16,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event: &'a OracleEvent,
+}
+
+impl OracleEvent {
+    pub fn emit(&self) {
+        let log = EventLog {
+            standard: EVENT_STANDARD,
+            version: EVENT_STANDARD_VERSION,
+            event: self,
+        };
+        env::log_str(&format!(
+            "EVENT_JSON:{}",
+            near_sdk::serde_json::to_string(&log).unwrap()
+        ));
+    }
 }
 
 #[near_bindgen]
@@ -148,8 +861,8 @@ pub struct Oracle {
     pub node_details: LookupMap<AccountId, OracleNode>,
     pub whitelisted_operators: UnorderedSet<AccountId>,
     pub approved_code_hashes: UnorderedSet<String>,
-    pub approved_enclaves: LookupMap<String, String>,
-    pub operator_to_node: LookupMap<AccountId, AccountId>,
+    pub approved_enclaves: LookupMap<String, EnclavePolicy>,
+    pub operator_nodes: LookupMap<AccountId, Vec<AccountId>>,
     pub node_to_operator: LookupMap<AccountId, AccountId>,
     pub recency_threshold: u64,
     pub min_report_count: u8,
@@ -160,13 +873,55 @@ pub struct Oracle {
     pub admin_quorum_bps: u16,
     pub proposals: UnorderedMap<u64, AdminProposal>,
     pub proposal_counter: u64,
+    pub twap_samples: LookupMap<String, Vec<Price>>,
+    pub price_history: LookupMap<(String, u64), HistoryEntry>,
+    pub history_cursors: LookupMap<String, HistoryCursor>,
+    pub history_depth: u32,
+    pub price_accumulators: LookupMap<String, PriceAccumulator>,
+    pub ema_period: u64,
+    pub open_rounds: LookupMap<String, PriceRound>,
+    pub round_counters: LookupMap<String, u64>,
+    pub round_duration: u64,
+    pub jail_policy: JailPolicy,
+    pub approved_root_certs: UnorderedSet<CryptoHash>,
+    pub attestation_collateral: AttestationCollateral,
+    pub reattestation_window: u64,
+    pub max_nodes_per_operator: u32,
+    pub pending_rotations: LookupMap<AccountId, NodeRotation>,
+    pub asset_nodes: LookupMap<String, Vec<AccountId>>,
+    pub bonds: LookupMap<AccountId, OperatorBond>,
+    pub staking_policy: StakingPolicy,
+    /// Receives slashed bonds.
+    pub treasury: AccountId,
+    pub fee_policy: FeePolicy,
+    /// Consumer -> end of its paid subscription.
+    pub subscriptions: LookupMap<AccountId, u64>,
+    /// Fees collected and not yet distributed.
+    pub reward_pool: NearToken,
+    /// Operator -> reports accepted into aggregates since the last distribution.
+    pub reward_points: LookupMap<AccountId, u64>,
+    pub total_reward_points: u64,
+    /// Operator -> distributed rewards not yet claimed.
+    pub rewards: LookupMap<AccountId, NearToken>,
+    pub price_subscriptions: LookupMap<String, Vec<PriceUpdateSubscription>>,
+    /// Subscriber -> prepaid balance for price update callbacks.
+    pub price_update_balances: LookupMap<AccountId, NearToken>,
+    pub reward_distribution: Option<RewardDistribution>,
+    /// Operator -> points the running distribution has yet to pay out.
+    pub distributing_points: LookupMap<AccountId, u64>,
+    /// Assets aggregated during the current call, whose subscribers are
+    /// notified once the reporting call is done. Not persisted.
+    #[borsh(skip)]
+    pub updated_assets: Vec<String>,
 }
 
 #[near_bindgen]
 impl Oracle {
     #[init]
     pub fn new(owner: AccountId, recency_threshold: u64, min_report_count: u8) -> Self {
+        migration::write_state_version(STATE_VERSION);
         Self {
+            treasury: owner.clone(),
             owner,
             paused: false,
             assets: UnorderedMap::new(StorageKey::Assets),
@@ -178,7 +933,7 @@ impl Oracle {
             whitelisted_operators: UnorderedSet::new(StorageKey::WhitelistedOperators),
             approved_code_hashes: UnorderedSet::new(StorageKey::ApprovedCodeHashes),
             approved_enclaves: LookupMap::new(StorageKey::ApprovedEnclaves),
-            operator_to_node: LookupMap::new(StorageKey::OperatorToNode),
+            operator_nodes: LookupMap::new(StorageKey::OperatorNodes),
             node_to_operator: LookupMap::new(StorageKey::NodeToOperator),
             recency_threshold,
             min_report_count: min_report_count.max(1),
@@ -189,16 +944,119 @@ impl Oracle {
             admin_quorum_bps: 5000,
             proposals: UnorderedMap::new(StorageKey::AdminProposals),
             proposal_counter: 0,
+            twap_samples: LookupMap::new(StorageKey::TwapSamples),
+            price_history: LookupMap::new(StorageKey::PriceHistory),
+            history_cursors: LookupMap::new(StorageKey::HistoryCursors),
+            history_depth: 100,
+            price_accumulators: LookupMap::new(StorageKey::PriceAccumulators),
+            ema_period: 3_600_000_000_000,
+            open_rounds: LookupMap::new(StorageKey::OpenRounds),
+            round_counters: LookupMap::new(StorageKey::RoundCounters),
+            round_duration: 60_000_000_000,
+            jail_policy: JailPolicy::default(),
+            approved_root_certs: UnorderedSet::new(StorageKey::ApprovedRootCerts),
+            attestation_collateral: AttestationCollateral::default(),
+            reattestation_window: 604_800_000_000_000,
+            max_nodes_per_operator: 3,
+            pending_rotations: LookupMap::new(StorageKey::PendingRotations),
+            asset_nodes: LookupMap::new(StorageKey::AssetNodes),
+            bonds: LookupMap::new(StorageKey::Bonds),
+            staking_policy: StakingPolicy::default(),
+            fee_policy: FeePolicy::default(),
+            subscriptions: LookupMap::new(StorageKey::Subscriptions),
+            reward_pool: NearToken::from_yoctonear(0),
+            reward_points: LookupMap::new(StorageKey::RewardPoints),
+            total_reward_points: 0,
+            rewards: LookupMap::new(StorageKey::Rewards),
+            price_subscriptions: LookupMap::new(StorageKey::PriceSubscriptions),
+            price_update_balances: LookupMap::new(StorageKey::PriceUpdateBalances),
+            reward_distribution: None,
+            distributing_points: LookupMap::new(StorageKey::DistributingPoints),
+            updated_assets: Vec::new(),
+        }
+    }
+
+    // Rewrites stored state into the current layout after new code is deployed.
+    // Callable by the contract itself (governance upgrade) or by the owner.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let from_version = migration::read_state_version();
+        let state = match from_version {
+            1 => migration::migrate_from_v1(env::state_read().expect("Failed to read state")),
+            2 => migration::migrate_from_v2(env::state_read().expect("Failed to read state")),
+            STATE_VERSION => env::state_read().expect("Failed to read state"),
+            _ => env::panic_str("Unsupported state version"),
+        };
+
+        let caller = env::predecessor_account_id();
+        assert!(
+            caller == env::current_account_id() || caller == state.owner,
+            "Only owner or the contract itself can migrate"
+        );
+
+        migration::write_state_version(STATE_VERSION);
+        OracleEvent::StateMigrated {
+            from_version,
+            to_version: STATE_VERSION,
         }
+        .emit();
+        state
     }
 
     // Admin functions
     pub fn add_asset(&mut self, asset: Asset) {
         self.assert_owner();
-        let asset_id = asset.id.clone();
-        self.assets.insert(&asset_id, &asset);
-        self.asset_list.push(&asset_id);
-        env::log_str(&format!("Asset added: {}", asset_id));
+        self.internal_add_asset(asset);
+    }
+
+    pub fn update_asset(&mut self, asset: Asset) {
+        self.assert_owner();
+        self.internal_update_asset(asset);
+    }
+
+    pub fn deactivate_asset(&mut self, asset_id: String) {
+        self.assert_owner();
+        self.internal_deactivate_asset(&asset_id);
+    }
+
+    pub fn remove_asset(&mut self, asset_id: String) {
+        self.assert_owner();
+        self.internal_remove_asset(&asset_id);
+    }
+
+    pub fn set_asset_status(&mut self, asset_id: String, status: AssetStatus) {
+        self.assert_owner();
+        self.internal_set_asset_status(&asset_id, status);
+    }
+
+    pub fn set_aggregation_method(&mut self, asset_id: String, method: AggregationMethod) {
+        self.assert_owner();
+        self.internal_set_aggregation_method(&asset_id, method);
+    }
+
+    pub fn set_asset_nodes(&mut self, asset_id: String, node_accounts: Vec<AccountId>) {
+        self.assert_owner();
+        self.internal_set_asset_nodes(&asset_id, node_accounts);
+    }
+
+    pub fn set_node_weight(&mut self, account_id: AccountId, weight: u32) {
+        self.assert_owner();
+        self.internal_set_node_weight(&account_id, weight);
+    }
+
+    pub fn suspend_node(&mut self, account_id: AccountId, duration: Option<u64>) {
+        self.assert_owner();
+        self.internal_suspend_node(&account_id, duration, SuspensionReason::Governance);
+    }
+
+    pub fn reinstate_node(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        self.internal_reinstate_node(&account_id);
+    }
+
+    pub fn set_jail_policy(&mut self, policy: JailPolicy) {
+        self.assert_owner();
+        self.internal_set_jail_policy(policy);
     }
 
     pub fn add_node_operator(&mut self, operator_account: AccountId) {
@@ -227,60 +1085,181 @@ impl Oracle {
             self.approved_code_hashes.contains(&code_hash),
             "Code hash must be approved first"
         );
-        self.approved_enclaves.insert(&code_hash, &mr_enclave);
-        env::log_str(&format!("Attestation approved for {}", code_hash));
+        self.internal_approve_attestation(code_hash, mr_enclave);
     }
 
     pub fn remove_attestation(&mut self, code_hash: String) {
         self.assert_owner();
-        self.approved_enclaves.remove(&code_hash);
-        env::log_str(&format!("Attestation removed for {}", code_hash));
-    }
-
-    pub fn pause(&mut self) {
-        self.assert_owner();
-        self.internal_pause();
+        self.internal_remove_attestation(code_hash);
     }
 
-    pub fn resume(&mut self) {
+    pub fn remove_enclave_measurement(&mut self, code_hash: String, mr_enclave: String) {
         self.assert_owner();
-        self.internal_resume();
+        self.internal_remove_enclave_measurement(code_hash, mr_enclave);
     }
 
-    pub fn update_config(&mut self, recency_threshold: Option<u64>, min_report_count: Option<u8>) {
+    pub fn set_signer_policy(&mut self, code_hash: String, policy: SignerPolicy) {
         self.assert_owner();
-        self.internal_update_config(recency_threshold, min_report_count);
+        assert!(
+            self.approved_code_hashes.contains(&code_hash),
+            "Code hash must be approved first"
+        );
+        self.internal_set_signer_policy(code_hash, policy);
+    }
+
+    pub fn pause(&mut self) {
+        self.assert_owner();
+        self.internal_pause();
+    }
+
+    pub fn resume(&mut self) {
+        self.assert_owner();
+        self.internal_resume();
+    }
+
+    pub fn update_config(
+        &mut self,
+        recency_threshold: Option<u64>,
+        min_report_count: Option<u8>,
+        history_depth: Option<u32>,
+        ema_period: Option<u64>,
+        round_duration: Option<u64>,
+    ) {
+        self.assert_owner();
+        self.internal_update_config(
+            recency_threshold,
+            min_report_count,
+            history_depth,
+            ema_period,
+            round_duration,
+        );
+    }
+
+    pub fn approve_root_certificate(&mut self, cert: Base64VecU8) {
+        self.assert_owner();
+        self.internal_approve_root_certificate(&cert.0);
+    }
+
+    pub fn remove_root_certificate(&mut self, cert_hash: Base58CryptoHash) {
+        self.assert_owner();
+        self.internal_remove_root_certificate(cert_hash);
+    }
+
+    pub fn set_attestation_collateral(&mut self, collateral: AttestationCollateral) {
+        self.assert_owner();
+        self.internal_set_attestation_collateral(collateral);
     }
 
     pub fn set_attestation_max_age(&mut self, max_age: u64) {
         self.assert_owner();
         assert!(max_age > 0, "Max age must be positive");
         self.attestation_max_age = max_age;
-        env::log_str(&format!("Attestation max age set to {} ns", max_age));
+        OracleEvent::AttestationMaxAgeSet { max_age }.emit();
     }
 
-    // Node operator functions
+    pub fn set_reattestation_window(&mut self, window: u64) {
+        self.assert_owner();
+        self.internal_set_reattestation_window(window);
+    }
+
+    pub fn set_max_nodes_per_operator(&mut self, max_nodes: u32) {
+        self.assert_owner();
+        self.internal_set_max_nodes_per_operator(max_nodes);
+    }
+
+    // Node operator functions. Adds a node to the operator's fleet, up to
+    // `max_nodes_per_operator`.
     pub fn set_node_account(&mut self, node_account: AccountId) {
         let operator_id = env::predecessor_account_id();
         assert!(
             self.whitelisted_operators.contains(&operator_id),
             "Not a whitelisted operator"
         );
-        if let Some(previous_node) = self.operator_to_node.get(&operator_id) {
-            self.node_to_operator.remove(&previous_node);
-            self.authorized_nodes.remove(&previous_node);
-            self.node_details.remove(&previous_node);
-        }
-        self.operator_to_node.insert(&operator_id, &node_account);
+        assert!(
+            self.node_to_operator.get(&node_account).is_none(),
+            "Node account already set"
+        );
+        let mut nodes = self.operator_nodes.get(&operator_id).unwrap_or_default();
+        assert!(
+            (nodes.len() as u32) < self.max_nodes_per_operator,
+            "Operator node limit reached"
+        );
+        nodes.push(node_account.clone());
+        self.operator_nodes.insert(&operator_id, &nodes);
         self.node_to_operator.insert(&node_account, &operator_id);
-        env::log_str(&format!(
-            "Node account set: {} -> {}",
-            operator_id, node_account
-        ));
+        OracleEvent::NodeAccountSet {
+            operator_id,
+            node_account,
+        }
+        .emit();
+    }
+
+    // First phase of a rotation: `new_node` joins the fleet, even above the
+    // cap, and replaces `old_node` once it registers.
+    pub fn stage_node_rotation(&mut self, old_node: AccountId, new_node: AccountId) {
+        let operator_id = env::predecessor_account_id();
+        assert!(
+            self.whitelisted_operators.contains(&operator_id),
+            "Not a whitelisted operator"
+        );
+        assert!(
+            self.node_to_operator.get(&old_node) == Some(operator_id.clone()),
+            "Node does not belong to operator"
+        );
+        assert!(
+            self.pending_rotations.get(&operator_id).is_none(),
+            "Rotation already staged"
+        );
+        assert!(
+            self.node_to_operator.get(&new_node).is_none(),
+            "Node account already set"
+        );
+
+        let mut nodes = self.operator_nodes.get(&operator_id).unwrap_or_default();
+        nodes.push(new_node.clone());
+        self.operator_nodes.insert(&operator_id, &nodes);
+        self.node_to_operator.insert(&new_node, &operator_id);
+        self.pending_rotations.insert(
+            &operator_id,
+            &NodeRotation {
+                old_node: old_node.clone(),
+                new_node: new_node.clone(),
+                staged_at: env::block_timestamp(),
+            },
+        );
+        OracleEvent::NodeRotationStaged {
+            operator_id,
+            old_node,
+            new_node,
+        }
+        .emit();
+    }
+
+    // Removes one of the caller's nodes. Retiring the staged node of a
+    // rotation cancels it.
+    pub fn retire_node(&mut self, node_account: AccountId) {
+        let operator_id = env::predecessor_account_id();
+        assert!(
+            self.node_to_operator.get(&node_account) == Some(operator_id.clone()),
+            "Node does not belong to operator"
+        );
+        if let Some(rotation) = self.pending_rotations.get(&operator_id) {
+            if rotation.old_node == node_account || rotation.new_node == node_account {
+                self.pending_rotations.remove(&operator_id);
+            }
+        }
+        self.detach_node(&operator_id, &node_account);
+        self.purge_node_reports(std::slice::from_ref(&node_account));
     }
 
-    // Node registration with attestation verification
-    pub fn register_node(&mut self, code_hash: String, attestation: AttestationData) {
+    // Node registration with attestation verification. The quote is bound to
+    // `signing_key` when given, otherwise to the key signing this transaction.
+    pub fn register_node(
+        &mut self,
+        code_hash: String,
+        attestation: AttestationData,
+        signing_key: Option<PublicKey>,
+    ) {
         let node_account = env::predecessor_account_id();
 
         // Check if this node account is authorized by an operator
@@ -300,34 +1279,98 @@ impl Oracle {
             "Code hash not approved"
         );
 
-        let expected_mr_enclave = self
+        let enclave_policy = self
             .approved_enclaves
             .get(&code_hash)
             .expect("Attestation not approved for code hash");
-        assert_eq!(
-            expected_mr_enclave, attestation.mr_enclave,
-            "Attestation measurement mismatch"
-        );
         let now = env::block_timestamp();
+        let quote = attestation::verify_quote(
+            &attestation.quote.0,
+            &self.approved_root_certs,
+            &self.attestation_collateral,
+            now,
+        )
+        .unwrap_or_else(|err| env::panic_str(&err));
+        enclave_policy
+            .check(&quote)
+            .unwrap_or_else(|err| env::panic_str(err));
+        let measurement = attestation::to_hex(&quote.measurement);
+
+        if let Some(key) = &signing_key {
+            assert!(
+                key.curve_type() == CurveType::ED25519,
+                "Signing key must be ed25519"
+            );
+        }
+        let bound_key = signing_key.clone().unwrap_or_else(env::signer_account_pk);
+        let key_hash = env::sha256(bound_key.as_bytes());
         assert!(
-            now.saturating_sub(attestation.issued_at) <= self.attestation_max_age,
+            quote.report_data[..32] == key_hash[..],
+            "Attestation is not bound to the signing key"
+        );
+        let issued_at = u64::from_le_bytes(quote.report_data[32..40].try_into().unwrap());
+        // A future issue time would postpone the node's re-attestation
+        assert!(issued_at <= now, "Attestation issued in the future");
+        assert!(
+            now.saturating_sub(issued_at) <= self.attestation_max_age,
             "Attestation expired"
         );
 
-        // Register node
-        let node = OracleNode {
-            account_id: node_account.clone(),
-            operator_id: operator,
-            registered_at: env::block_timestamp(),
-            code_hash,
-            last_report: 0,
-            active: true,
+        // Re-registration (e.g. a restarted enclave with a fresh key) keeps the
+        // node's history, weight and nonce
+        let node = match self.node_details.get(&node_account) {
+            Some(existing) => {
+                assert!(
+                    existing.active || now >= existing.suspended_until,
+                    "Node is suspended"
+                );
+                OracleNode {
+                    operator_id: operator.clone(),
+                    registered_at: now,
+                    code_hash: code_hash.clone(),
+                    active: true,
+                    suspended_until: 0,
+                    signing_key: signing_key.clone(),
+                    attested_at: issued_at,
+                    measurement: measurement.clone(),
+                    ..existing
+                }
+            }
+            None => OracleNode {
+                account_id: node_account.clone(),
+                operator_id: operator.clone(),
+                registered_at: now,
+                code_hash: code_hash.clone(),
+                last_report: 0,
+                active: true,
+                outlier_reports: 0,
+                weight: 1,
+                suspended_until: 0,
+                consecutive_outliers: 0,
+                signing_key: signing_key.clone(),
+                attested_at: issued_at,
+                measurement: measurement.clone(),
+                reputation: NodeReputation::default(),
+                last_nonce: 0,
+            },
         };
 
         self.authorized_nodes.insert(&node_account);
         self.node_details.insert(&node_account, &node);
 
-        env::log_str(&format!("Node registered: {}", node_account));
+        OracleEvent::NodeRegistered {
+            node_account: node_account.clone(),
+            operator_id: operator.clone(),
+            code_hash,
+            signing_key,
+        }
+        .emit();
+
+        if let Some(rotation) = self.pending_rotations.get(&operator) {
+            if rotation.new_node == node_account {
+                self.complete_rotation(&operator, rotation);
+            }
+        }
     }
 
     // Price reporting
@@ -335,52 +1378,112 @@ impl Oracle {
         assert!(!self.paused, "Oracle is paused");
 
         let node_account = env::predecessor_account_id();
-        assert!(
-            self.authorized_nodes.contains(&node_account),
-            "Not an authorized node"
-        );
+        let timestamp = env::block_timestamp();
+        self.assert_reporting_node(&node_account, timestamp);
 
-        // Verify asset exists and decimals align
+        if let Err(reason) =
+            self.internal_report_price(&node_account, asset_id, multiplier, decimals, timestamp)
+        {
+            env::panic_str(&reason);
+        }
+        self.touch_node(&node_account, timestamp);
+        self.deliver_price_updates();
+    }
+
+    // Closes an open round whose deadline has passed. Callable by anyone so a
+    // round missing some reporters does not wait for the next report.
+    pub fn finalize_round(&mut self, asset_id: String) {
         let asset = self.assets.get(&asset_id).expect("Asset not found");
-        assert_eq!(
-            asset.decimals, decimals,
-            "Decimals mismatch with asset definition"
+        let round = self
+            .open_rounds
+            .get(&asset_id)
+            .expect("No open round for asset");
+        assert!(
+            env::block_timestamp() > round.deadline,
+            "Round deadline has not passed"
         );
+        self.close_round(&asset_id, &asset, round);
+        self.deliver_price_updates();
+    }
 
-        let timestamp = env::block_timestamp();
-
-        // Update reports for this asset
-        let mut reports = self.price_reports.get(&asset_id).unwrap_or_default();
-
-        // Replace existing report from this oracle
-        reports.retain(|r| r.oracle_id != node_account);
+    // Reports several assets in one call
+    pub fn report_prices(&mut self, prices: Vec<PriceInput>) -> BatchReportResult {
+        assert!(!self.paused, "Oracle is paused");
 
-        reports.push(PriceReport {
-            oracle_id: node_account.clone(),
-            price: Price {
-                multiplier,
-                decimals,
-                timestamp,
-            },
-            timestamp,
-        });
+        let node_account = env::predecessor_account_id();
+        let timestamp = env::block_timestamp();
+        self.assert_reporting_node(&node_account, timestamp);
+        let result = self.internal_report_prices(&node_account, prices, timestamp);
+        self.deliver_price_updates();
+        result
+    }
 
-        self.finalize_reports(asset_id.clone(), &asset, reports);
+    // Reports a batch signed by the node's enclave key. Callable by anyone,
+    // so relayers can submit on behalf of nodes.
+    pub fn report_price_signed(
+        &mut self,
+        payload: SignedPriceReport,
+        signature: Base64VecU8,
+    ) -> BatchReportResult {
+        assert!(!self.paused, "Oracle is paused");
+        assert_eq!(
+            payload.contract_id,
+            env::current_account_id(),
+            "Report is for another contract"
+        );
 
-        // Update node's last report time
-        if let Some(mut node) = self.node_details.get(&node_account) {
-            node.last_report = timestamp;
-            self.node_details.insert(&node_account, &node);
+        let node_account = payload.node_account.clone();
+        let timestamp = env::block_timestamp();
+        self.assert_reporting_node(&node_account, timestamp);
+
+        let mut node = self.node_details.get(&node_account).unwrap();
+        let key = node.signing_key.as_ref().expect("Node has no signing key");
+        let signature: [u8; 64] = signature
+            .0
+            .try_into()
+            .unwrap_or_else(|_| env::panic_str("Invalid report signature"));
+        let key: [u8; 32] = key.as_bytes()[1..].try_into().unwrap();
+        assert!(
+            env::ed25519_verify(&signature, &borsh::to_vec(&payload).unwrap(), &key),
+            "Invalid report signature"
+        );
+        assert!(payload.nonce > node.last_nonce, "Report nonce already used");
+        if self.recency_threshold > 0 {
+            assert!(
+                timestamp.saturating_sub(payload.signed_at) <= self.recency_threshold,
+                "Signed report is too old"
+            );
         }
 
-        env::log_str(&format!(
-            "Price reported for {} by {}",
-            asset_id, node_account
-        ));
+        node.last_nonce = payload.nonce;
+        self.node_details.insert(&node_account, &node);
+        let result = self.internal_report_prices(&node_account, payload.prices, timestamp);
+        self.deliver_price_updates();
+        result
+    }
+
+    // Suspends a node that has not reported within the heartbeat timeout.
+    // Callable by anyone.
+    pub fn jail_idle_node(&mut self, account_id: AccountId) {
+        let timeout = self.jail_policy.heartbeat_timeout;
+        assert!(timeout > 0, "Heartbeat jailing is disabled");
+        let node = self.node_details.get(&account_id).expect("Node not found");
+        assert!(node.active, "Node is already suspended");
+        let last_seen = node.last_report.max(node.registered_at);
+        assert!(
+            env::block_timestamp().saturating_sub(last_seen) > timeout,
+            "Node has reported within the heartbeat timeout"
+        );
+        self.internal_suspend_node(
+            &account_id,
+            Some(self.jail_policy.jail_duration),
+            SuspensionReason::MissedHeartbeat,
+        );
     }
 
     // Query functions
     pub fn get_price(&self, asset_id: String) -> Option<PriceData> {
+        self.queryable_asset(&asset_id)?;
         let price = self.aggregated_prices.get(&asset_id)?;
 
         if self.recency_threshold > 0 {
@@ -391,38 +1494,32 @@ impl Oracle {
         }
 
         let reports = self.price_reports.get(&asset_id).unwrap_or_default();
+        let accepted: Vec<PriceReport> = reports.into_iter().filter(|r| !r.flagged).collect();
 
         Some(PriceData {
             asset_id,
+            round_id: price.round_id,
             price,
-            num_sources: reports.len() as u8,
+            num_sources: self.latest_per_operator(&accepted).len() as u8,
         })
     }
 
     pub fn get_price_no_older_than(&self, asset_id: String, max_age: u64) -> Option<PythPrice> {
+        self.queryable_asset(&asset_id)?;
         let price = self.aggregated_prices.get(&asset_id)?;
 
         if env::block_timestamp().saturating_sub(price.timestamp) > max_age {
             return None;
         }
 
-        Some(PythPrice {
-            price: price.multiplier as i64,
-            conf: 0,
-            expo: -(price.decimals as i32),
-            publish_time: (price.timestamp / 1_000_000_000) as i64,
-        })
+        Some(PythPrice::from(&price))
     }
 
     pub fn get_price_unsafe(&self, asset_id: String) -> Option<PythPrice> {
+        self.queryable_asset(&asset_id)?;
         let price = self.aggregated_prices.get(&asset_id)?;
 
-        Some(PythPrice {
-            price: price.multiplier as i64,
-            conf: 0,
-            expo: -(price.decimals as i32),
-            publish_time: (price.timestamp / 1_000_000_000) as i64,
-        })
+        Some(PythPrice::from(&price))
     }
 
     pub fn get_price_data(&self) -> Vec<PriceData> {
@@ -437,6 +1534,90 @@ impl Oracle {
         result
     }
 
+    pub fn get_price_at(&self, asset_id: String, timestamp: u64) -> Option<Price> {
+        self.history_entry_at(&asset_id, timestamp)
+            .map(|entry| entry.price)
+    }
+
+    pub fn get_price_history(&self, asset_id: String, from: u64, limit: u32) -> Vec<Price> {
+        let cursor = match self.history_cursors.get(&asset_id) {
+            Some(cursor) => cursor,
+            None => return Vec::new(),
+        };
+
+        let mut result = Vec::new();
+        for seq in cursor.oldest..cursor.next {
+            if result.len() >= limit as usize {
+                break;
+            }
+            if let Some(entry) = self.price_history.get(&(asset_id.clone(), seq)) {
+                if entry.price.timestamp >= from {
+                    result.push(entry.price);
+                }
+            }
+        }
+        result
+    }
+
+    pub fn get_twap(&self, asset_id: String, window_ns: u64) -> Option<Price> {
+        self.queryable_asset(&asset_id)?;
+        self.aggregated_prices.get(&asset_id)?;
+        let accumulator = self.price_accumulators.get(&asset_id)?;
+        let now = env::block_timestamp();
+        if window_ns == 0 || window_ns > now {
+            return None;
+        }
+
+        // Needs history reaching back to the start of the window
+        let start = now - window_ns;
+        let entry = self.history_entry_at(&asset_id, start)?;
+        let cumulative_start = entry.cumulative.saturating_add(
+            entry
+                .price
+                .multiplier
+                .saturating_mul((start - entry.price.timestamp) as u128),
+        );
+        let cumulative_now = accumulator.cumulative.saturating_add(
+            accumulator
+                .last_price
+                .saturating_mul(now.saturating_sub(accumulator.last_timestamp) as u128),
+        );
+
+        Some(Price {
+            multiplier: cumulative_now.saturating_sub(cumulative_start) / window_ns as u128,
+            decimals: accumulator.decimals,
+            timestamp: accumulator.last_timestamp,
+            conf: 0,
+            round_id: 0,
+        })
+    }
+
+    pub fn get_ema_price_no_older_than(&self, asset_id: String, max_age: u64) -> Option<PythPrice> {
+        self.queryable_asset(&asset_id)?;
+        let accumulator = self.price_accumulators.get(&asset_id)?;
+        let now = env::block_timestamp();
+        if now.saturating_sub(accumulator.last_timestamp) > max_age {
+            return None;
+        }
+        Some(PythPrice::from(&self.ema_at(&accumulator, now)))
+    }
+
+    pub fn get_ema_price_unsafe(&self, asset_id: String) -> Option<PythPrice> {
+        self.queryable_asset(&asset_id)?;
+        let accumulator = self.price_accumulators.get(&asset_id)?;
+        Some(PythPrice::from(
+            &self.ema_at(&accumulator, env::block_timestamp()),
+        ))
+    }
+
+    pub fn get_open_round(&self, asset_id: String) -> Option<PriceRound> {
+        self.open_rounds.get(&asset_id)
+    }
+
+    pub fn get_price_reports(&self, asset_id: String) -> Vec<PriceReport> {
+        self.price_reports.get(&asset_id).unwrap_or_default()
+    }
+
     pub fn get_assets(&self) -> Vec<Asset> {
         let mut result = Vec::new();
         for i in 0..self.asset_list.len() {
@@ -449,6 +1630,43 @@ impl Oracle {
         result
     }
 
+    // Assigned nodes, empty when the asset is open to every node
+    pub fn get_asset_nodes(&self, asset_id: String) -> Vec<AccountId> {
+        self.asset_nodes.get(&asset_id).unwrap_or_default()
+    }
+
+    pub fn get_asset_coverage(&self, asset_id: String) -> Option<AssetCoverage> {
+        let asset = self.assets.get(&asset_id)?;
+        let eligible_nodes = self.eligible_nodes(&asset_id);
+        let minimum_timestamp = env::block_timestamp().saturating_sub(self.recency_threshold);
+        let reporting_nodes = self
+            .price_reports
+            .get(&asset_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|r| {
+                eligible_nodes.contains(&r.oracle_id)
+                    && (self.recency_threshold == 0 || r.timestamp >= minimum_timestamp)
+            })
+            .map(|r| r.oracle_id)
+            .collect();
+        Some(AssetCoverage {
+            assigned: self.asset_nodes.get(&asset_id).is_some(),
+            required_sources: self.required_sources(&asset) as u8,
+            asset_id,
+            eligible_nodes,
+            reporting_nodes,
+        })
+    }
+
+    // Assets the node may report
+    pub fn get_node_assets(&self, account_id: AccountId) -> Vec<String> {
+        self.asset_list
+            .iter()
+            .filter(|asset_id| self.is_assigned(asset_id, &account_id))
+            .collect()
+    }
+
     pub fn is_authorized(&self, account_id: AccountId) -> bool {
         self.authorized_nodes.contains(&account_id)
     }
@@ -461,6 +1679,84 @@ impl Oracle {
         self.authorized_nodes.iter().collect()
     }
 
+    pub fn get_operator_nodes(&self, operator_id: AccountId) -> Vec<AccountId> {
+        self.operator_nodes.get(&operator_id).unwrap_or_default()
+    }
+
+    // Details of the operator's registered nodes
+    pub fn get_operator_fleet(&self, operator_id: AccountId) -> Vec<OracleNode> {
+        self.get_operator_nodes(operator_id)
+            .iter()
+            .filter_map(|account_id| self.node_details.get(account_id))
+            .collect()
+    }
+
+    pub fn get_max_nodes_per_operator(&self) -> u32 {
+        self.max_nodes_per_operator
+    }
+
+    pub fn get_pending_rotation(&self, operator_id: AccountId) -> Option<NodeRotation> {
+        self.pending_rotations.get(&operator_id)
+    }
+
+    pub fn get_node_attestation(&self, account_id: AccountId) -> Option<NodeAttestationView> {
+        let node = self.node_details.get(&account_id)?;
+        let expires_at = self.attestation_expiry(&node);
+        Some(NodeAttestationView {
+            lapsed: self.attestation_lapsed(&node, env::block_timestamp()),
+            code_hash: node.code_hash,
+            attested_at: node.attested_at,
+            expires_at,
+        })
+    }
+
+    // Authorized nodes that must re-attest before they can report again
+    pub fn get_lapsed_nodes(&self) -> Vec<AccountId> {
+        let now = env::block_timestamp();
+        self.authorized_nodes
+            .iter()
+            .filter(|account_id| {
+                self.node_details
+                    .get(account_id)
+                    .is_some_and(|node| self.attestation_lapsed(&node, now))
+            })
+            .collect()
+    }
+
+    pub fn get_reattestation_window(&self) -> u64 {
+        self.reattestation_window
+    }
+
+    pub fn get_enclave_policy(&self, code_hash: String) -> Option<EnclavePolicy> {
+        self.approved_enclaves.get(&code_hash)
+    }
+
+    pub fn get_root_certificates(&self) -> Vec<Base58CryptoHash> {
+        self.approved_root_certs
+            .iter()
+            .map(Base58CryptoHash::from)
+            .collect()
+    }
+
+    pub fn get_attestation_collateral(&self) -> AttestationCollateral {
+        self.attestation_collateral.clone()
+    }
+
+    pub fn get_jail_policy(&self) -> JailPolicy {
+        self.jail_policy.clone()
+    }
+
+    pub fn get_state_version(&self) -> u16 {
+        migration::read_state_version()
+    }
+
+    pub fn get_upgrade_code_hash(&self, proposal_id: u64) -> Option<Base58CryptoHash> {
+        match self.proposals.get(&proposal_id)?.action {
+            AdminAction::UpgradeContract { code } => Some(code_hash_of(&code.0)),
+            _ => None,
+        }
+    }
+
     pub fn get_admin_role(&self) -> AdminRoleView {
         AdminRoleView {
             proposers: self.admin_proposers.iter().collect(),
@@ -511,7 +1807,13 @@ impl Oracle {
         self.admin_timelock_delay = timelock_delay;
         self.admin_quorum_bps = if quorum_bps == 0 { 1 } else { quorum_bps };
 
-        env::log_str("Admin role configured");
+        OracleEvent::AdminRoleConfigured {
+            proposers: self.admin_proposers.to_vec(),
+            voters: self.admin_voters.to_vec(),
+            timelock_delay: self.admin_timelock_delay,
+            quorum_bps: self.admin_quorum_bps,
+        }
+        .emit();
     }
 
     pub fn propose_action(&mut self, action: AdminAction) {
@@ -533,17 +1835,20 @@ impl Oracle {
         let proposal = AdminProposal {
             id: proposal_id,
             proposer: caller.clone(),
-            action,
+            action: action.clone(),
             scheduled_for,
             approvals,
             executed: false,
         };
 
         self.proposals.insert(&proposal_id, &proposal);
-        env::log_str(&format!(
-            "Admin action proposed by {}: {}",
-            caller, proposal_id
-        ));
+        OracleEvent::ProposalCreated {
+            proposal_id,
+            proposer: caller,
+            action,
+            scheduled_for,
+        }
+        .emit();
     }
 
     pub fn approve_proposal(&mut self, proposal_id: u64) {
@@ -559,14 +1864,18 @@ impl Oracle {
             .expect("Proposal not found");
         assert!(!proposal.executed, "Proposal already executed");
 
+        // Approving twice is a no-op
         if proposal.approvals.contains(&caller) {
-            env::log_str("Voter already approved proposal");
             return;
         }
 
         proposal.approvals.push(caller.clone());
         self.proposals.insert(&proposal_id, &proposal);
-        env::log_str(&format!("Proposal {} approved by {}", proposal_id, caller));
+        OracleEvent::ProposalApproved {
+            proposal_id,
+            voter: caller,
+        }
+        .emit();
     }
 
     pub fn execute_proposal(&mut self, proposal_id: u64) {
@@ -592,7 +1901,11 @@ impl Oracle {
 
         self.execute_admin_action(&proposal.action);
         self.proposals.remove(&proposal_id);
-        env::log_str(&format!("Proposal {} executed by {}", proposal_id, caller));
+        OracleEvent::ProposalExecuted {
+            proposal_id,
+            executor: caller,
+        }
+        .emit();
     }
 
     pub fn cancel_proposal(&mut self, proposal_id: u64) {
@@ -600,7 +1913,7 @@ impl Oracle {
         self.proposals
             .remove(&proposal_id)
             .expect("Proposal not found");
-        env::log_str(&format!("Proposal {} cancelled", proposal_id));
+        OracleEvent::ProposalCancelled { proposal_id }.emit();
     }
 
     // Internal helper functions
@@ -622,100 +1935,474 @@ impl Oracle {
                 code_hash,
                 mr_enclave,
             } => {
-                self.approved_enclaves.insert(&code_hash, &mr_enclave);
-                env::log_str(&format!(
-                    "Attestation approved via governance for {}",
-                    code_hash
-                ));
+                self.internal_approve_attestation(code_hash.clone(), mr_enclave.clone());
             }
             AdminAction::RemoveAttestation { code_hash } => {
-                self.approved_enclaves.remove(&code_hash);
-                env::log_str(&format!(
-                    "Attestation removed via governance for {}",
-                    code_hash
-                ));
+                self.internal_remove_attestation(code_hash.clone());
+            }
+            AdminAction::RemoveEnclaveMeasurement {
+                code_hash,
+                mr_enclave,
+            } => {
+                self.internal_remove_enclave_measurement(code_hash.clone(), mr_enclave.clone());
+            }
+            AdminAction::SetSignerPolicy { code_hash, policy } => {
+                self.internal_set_signer_policy(code_hash.clone(), policy.clone());
             }
             AdminAction::Pause => self.internal_pause(),
             AdminAction::Resume => self.internal_resume(),
             AdminAction::UpdateConfig {
                 recency_threshold,
                 min_report_count,
+                history_depth,
+                ema_period,
+                round_duration,
             } => {
-                self.internal_update_config(recency_threshold.clone(), min_report_count.clone());
+                self.internal_update_config(
+                    *recency_threshold,
+                    *min_report_count,
+                    *history_depth,
+                    *ema_period,
+                    *round_duration,
+                );
             }
-        }
-    }
-
-    fn has_quorum(&self, approvals_len: usize) -> bool {
-        approvals_len >= self.required_approvals()
-    }
-
-    fn required_approvals(&self) -> usize {
-        let voter_count = self.admin_voters.len() as u128;
-        if voter_count == 0 {
-            return 0;
-        }
-        let quorum_bps = self.admin_quorum_bps as u128;
-        let mut required = (voter_count * quorum_bps + 9_999) / 10_000;
-        if required == 0 {
-            required = 1;
-        }
-        required as usize
-    }
+            AdminAction::AddAsset { asset } => self.internal_add_asset(asset.clone()),
+            AdminAction::UpdateAsset { asset } => self.internal_update_asset(asset.clone()),
+            AdminAction::DeactivateAsset { asset_id } => self.internal_deactivate_asset(asset_id),
+            AdminAction::RemoveAsset { asset_id } => self.internal_remove_asset(asset_id),
+            AdminAction::SetAssetStatus { asset_id, status } => {
+                self.internal_set_asset_status(asset_id, status.clone());
+            }
+            AdminAction::SetAggregationMethod { asset_id, method } => {
+                self.internal_set_aggregation_method(asset_id, method.clone());
+            }
+            AdminAction::SetAssetNodes {
+                asset_id,
+                node_accounts,
+            } => {
+                self.internal_set_asset_nodes(asset_id, node_accounts.clone());
+            }
+            AdminAction::SetNodeWeight { account_id, weight } => {
+                self.internal_set_node_weight(account_id, *weight);
+            }
+            AdminAction::SuspendNode {
+                account_id,
+                duration,
+            } => {
+                self.internal_suspend_node(account_id, *duration, SuspensionReason::Governance);
+            }
+            AdminAction::ReinstateNode { account_id } => {
+                self.internal_reinstate_node(account_id);
+            }
+            AdminAction::SetJailPolicy { policy } => {
+                self.internal_set_jail_policy(policy.clone());
+            }
+            AdminAction::SetReattestationWindow { window } => {
+                self.internal_set_reattestation_window(*window);
+            }
+            AdminAction::SetMaxNodesPerOperator { max_nodes } => {
+                self.internal_set_max_nodes_per_operator(*max_nodes);
+            }
+            AdminAction::ApproveRootCertificate { cert } => {
+                self.internal_approve_root_certificate(&cert.0);
+            }
+            AdminAction::RemoveRootCertificate { cert_hash } => {
+                self.internal_remove_root_certificate(*cert_hash);
+            }
+            AdminAction::SetAttestationCollateral { collateral } => {
+                self.internal_set_attestation_collateral(collateral.clone());
+            }
+            AdminAction::SlashOperator {
+                operator_id,
+                amount,
+                reason,
+            } => {
+                self.internal_slash_operator(operator_id, *amount, reason.clone());
+            }
+            AdminAction::SetStakingPolicy { policy } => {
+                self.internal_set_staking_policy(policy.clone());
+            }
+            AdminAction::SetTreasury { account_id } => {
+                self.internal_set_treasury(account_id.clone());
+            }
+            AdminAction::SetFeePolicy { policy } => {
+                self.internal_set_fee_policy(policy.clone());
+            }
+            AdminAction::UpgradeContract { code } => {
+                self.internal_upgrade_contract(code.0.clone());
+            }
+        }
+    }
+
+    fn has_quorum(&self, approvals_len: usize) -> bool {
+        approvals_len >= self.required_approvals()
+    }
+
+    fn required_approvals(&self) -> usize {
+        let voter_count = self.admin_voters.len() as u128;
+        if voter_count == 0 {
+            return 0;
+        }
+        let quorum_bps = self.admin_quorum_bps as u128;
+        let mut required = (voter_count * quorum_bps).div_ceil(10_000);
+        if required == 0 {
+            required = 1;
+        }
+        required as usize
+    }
 
     fn internal_add_node_operator(&mut self, operator_account: AccountId) {
         self.whitelisted_operators.insert(&operator_account);
-        env::log_str(&format!("Node operator added: {}", operator_account));
+        OracleEvent::NodeOperatorAdded {
+            operator_id: operator_account,
+        }
+        .emit();
     }
 
     fn internal_remove_node_operator(&mut self, operator_account: &AccountId) {
+        self.forfeit_reward_points(operator_account);
         self.whitelisted_operators.remove(operator_account);
-        if let Some(node_account) = self.operator_to_node.get(operator_account) {
-            self.operator_to_node.remove(operator_account);
-            self.node_to_operator.remove(&node_account);
-            self.authorized_nodes.remove(&node_account);
-            self.node_details.remove(&node_account);
-
-            for i in 0..self.asset_list.len() {
-                if let Some(asset_id) = self.asset_list.get(i) {
-                    if let Some(mut reports) = self.price_reports.get(&asset_id) {
-                        reports.retain(|r| r.oracle_id != node_account);
-
-                        if let Some(asset) = self.assets.get(&asset_id) {
-                            self.finalize_reports(asset_id.clone(), &asset, reports);
+        self.pending_rotations.remove(operator_account);
+        let node_accounts = self.get_operator_nodes(operator_account.clone());
+        for node_account in &node_accounts {
+            self.detach_node(operator_account, node_account);
+        }
+        if !node_accounts.is_empty() {
+            self.purge_node_reports(&node_accounts);
+        }
+        OracleEvent::NodeOperatorRemoved {
+            operator_id: operator_account.clone(),
+        }
+        .emit();
+    }
+
+    // Unlinks a node from its operator and deletes its registration; callers
+    // purge its reports.
+    fn detach_node(&mut self, operator_id: &AccountId, node_account: &AccountId) {
+        let mut nodes = self.operator_nodes.get(operator_id).unwrap_or_default();
+        nodes.retain(|account_id| account_id != node_account);
+        if nodes.is_empty() {
+            self.operator_nodes.remove(operator_id);
+        } else {
+            self.operator_nodes.insert(operator_id, &nodes);
+        }
+        self.node_to_operator.remove(node_account);
+        self.authorized_nodes.remove(node_account);
+        self.node_details.remove(node_account);
+        OracleEvent::NodeRemoved {
+            node_account: node_account.clone(),
+            operator_id: operator_id.clone(),
+        }
+        .emit();
+    }
+
+    // Retires the old node once its replacement has registered; the new node
+    // takes over the old one's governance-set weight, reputation and asset
+    // assignments. The current price is left as is so the operator stays
+    // counted until the next aggregation.
+    fn complete_rotation(&mut self, operator_id: &AccountId, rotation: NodeRotation) {
+        self.pending_rotations.remove(operator_id);
+        if let Some(old_node) = self.node_details.get(&rotation.old_node) {
+            let mut new_node = self
+                .node_details
+                .get(&rotation.new_node)
+                .expect("Node not found");
+            new_node.weight = old_node.weight;
+            new_node.reputation = old_node.reputation;
+            self.node_details.insert(&rotation.new_node, &new_node);
+        }
+        self.reassign_asset_nodes(&rotation.old_node, &rotation.new_node);
+        if self.node_to_operator.get(&rotation.old_node).is_some() {
+            self.detach_node(operator_id, &rotation.old_node);
+        }
+        OracleEvent::NodeRotated {
+            operator_id: operator_id.clone(),
+            old_node: rotation.old_node,
+            new_node: rotation.new_node,
+        }
+        .emit();
+    }
+
+    fn reassign_asset_nodes(&mut self, old_node: &AccountId, new_node: &AccountId) {
+        for asset_id in self.asset_list.to_vec() {
+            let Some(mut node_accounts) = self.asset_nodes.get(&asset_id) else {
+                continue;
+            };
+            if !node_accounts.contains(old_node) {
+                continue;
+            }
+            for account_id in node_accounts.iter_mut() {
+                if account_id == old_node {
+                    *account_id = new_node.clone();
+                }
+            }
+            node_accounts.sort();
+            node_accounts.dedup();
+            self.asset_nodes.insert(&asset_id, &node_accounts);
+            OracleEvent::AssetNodesSet {
+                asset_id,
+                node_accounts,
+            }
+            .emit();
+        }
+    }
+
+    // Drops the nodes' reports from open rounds and re-aggregates every asset
+    // whose current price included them.
+    fn purge_node_reports(&mut self, node_accounts: &[AccountId]) {
+        for i in 0..self.asset_list.len() {
+            if let Some(asset_id) = self.asset_list.get(i) {
+                if let Some(mut round) = self.open_rounds.get(&asset_id) {
+                    round
+                        .reports
+                        .retain(|r| !node_accounts.contains(&r.oracle_id));
+                    self.open_rounds.insert(&asset_id, &round);
+                }
+                if let Some(mut reports) = self.price_reports.get(&asset_id) {
+                    reports.retain(|r| !node_accounts.contains(&r.oracle_id));
+
+                    self.price_reports.insert(&asset_id, &reports);
+                    if let Some(asset) = self.assets.get(&asset_id) {
+                        let accepted = self.finalize_reports(asset_id.clone(), &asset, reports);
+                        // The previous aggregate counted the purged nodes
+                        if accepted.is_empty() && asset.status.accepts_reports() {
+                            self.aggregated_prices.remove(&asset_id);
                         }
                     }
                 }
             }
         }
-        env::log_str(&format!("Node operator removed: {}", operator_account));
+    }
+
+    fn internal_suspend_node(
+        &mut self,
+        account_id: &AccountId,
+        duration: Option<u64>,
+        reason: SuspensionReason,
+    ) {
+        let node = self.node_details.get(account_id).expect("Node not found");
+        let until = duration
+            .map(|duration| env::block_timestamp().saturating_add(duration))
+            .unwrap_or(u64::MAX);
+        self.jail_node(node, until, reason);
+        self.purge_node_reports(std::slice::from_ref(account_id));
+    }
+
+    // Marks the node suspended without touching stored reports; callers
+    // decide whether its existing reports need purging.
+    fn jail_node(&mut self, mut node: OracleNode, until: u64, reason: SuspensionReason) {
+        node.active = false;
+        node.suspended_until = until;
+        self.node_details.insert(&node.account_id, &node);
+        OracleEvent::NodeSuspended {
+            node_account: node.account_id,
+            until,
+            reason,
+        }
+        .emit();
+    }
+
+    fn internal_reinstate_node(&mut self, account_id: &AccountId) {
+        let mut node = self.node_details.get(account_id).expect("Node not found");
+        assert!(!node.active, "Node is not suspended");
+        node.active = true;
+        node.suspended_until = 0;
+        node.consecutive_outliers = 0;
+        self.node_details.insert(account_id, &node);
+        OracleEvent::NodeReinstated {
+            node_account: account_id.clone(),
+        }
+        .emit();
+    }
+
+    fn internal_set_jail_policy(&mut self, policy: JailPolicy) {
+        assert!(policy.jail_duration > 0, "Jail duration must be positive");
+        OracleEvent::JailPolicySet {
+            jail_duration: policy.jail_duration,
+            max_consecutive_outliers: policy.max_consecutive_outliers,
+            heartbeat_timeout: policy.heartbeat_timeout,
+        }
+        .emit();
+        self.jail_policy = policy;
+    }
+
+    fn internal_set_reattestation_window(&mut self, window: u64) {
+        self.reattestation_window = window;
+        OracleEvent::ReattestationWindowSet { window }.emit();
+    }
+
+    // Lowering the cap keeps existing nodes; it only blocks new ones
+    fn internal_set_max_nodes_per_operator(&mut self, max_nodes: u32) {
+        assert!(max_nodes > 0, "Operators need at least one node");
+        self.max_nodes_per_operator = max_nodes;
+        OracleEvent::MaxNodesPerOperatorSet { max_nodes }.emit();
     }
 
     fn internal_approve_code_hash(&mut self, code_hash: String) {
         self.approved_code_hashes.insert(&code_hash);
-        env::log_str(&format!("Code hash approved: {}", code_hash));
+        OracleEvent::CodeHashApproved { code_hash }.emit();
     }
 
     fn internal_remove_code_hash(&mut self, code_hash: &String) {
         self.approved_code_hashes.remove(code_hash);
         self.approved_enclaves.remove(code_hash);
-        env::log_str(&format!("Code hash removed: {}", code_hash));
+        OracleEvent::CodeHashRemoved {
+            code_hash: code_hash.clone(),
+        }
+        .emit();
+        self.deauthorize_nodes(code_hash, None);
+    }
+
+    // Adds a measurement to the code hash's approved set
+    fn internal_approve_attestation(&mut self, code_hash: String, mr_enclave: String) {
+        let mr_enclave = mr_enclave.to_ascii_lowercase();
+        let mut policy = self.approved_enclaves.get(&code_hash).unwrap_or_default();
+        if !policy.measurements.contains(&mr_enclave) {
+            policy.measurements.push(mr_enclave.clone());
+        }
+        self.approved_enclaves.insert(&code_hash, &policy);
+        OracleEvent::AttestationApproved {
+            code_hash,
+            mr_enclave,
+        }
+        .emit();
+    }
+
+    fn internal_remove_attestation(&mut self, code_hash: String) {
+        self.approved_enclaves.remove(&code_hash);
+        OracleEvent::AttestationRemoved {
+            code_hash: code_hash.clone(),
+        }
+        .emit();
+        self.deauthorize_nodes(&code_hash, None);
+    }
+
+    // Removes every node registered with `code_hash` (and `mr_enclave`, when
+    // given) from the authorized set and drops its reports. Node details and
+    // the operator link stay, so the node can re-register with an approved build.
+    fn deauthorize_nodes(&mut self, code_hash: &str, mr_enclave: Option<&str>) {
+        let node_accounts: Vec<AccountId> = self
+            .authorized_nodes
+            .iter()
+            .filter(|account_id| {
+                self.node_details.get(account_id).is_some_and(|node| {
+                    node.code_hash == code_hash
+                        && mr_enclave.is_none_or(|measurement| node.measurement == measurement)
+                })
+            })
+            .collect();
+        if node_accounts.is_empty() {
+            return;
+        }
+
+        for account_id in &node_accounts {
+            self.authorized_nodes.remove(account_id);
+        }
+        OracleEvent::NodesDeauthorized {
+            code_hash: code_hash.to_string(),
+            mr_enclave: mr_enclave.map(str::to_string),
+            node_accounts: node_accounts.clone(),
+        }
+        .emit();
+        self.purge_node_reports(&node_accounts);
+    }
+
+    fn internal_remove_enclave_measurement(&mut self, code_hash: String, mr_enclave: String) {
+        let mut policy = self
+            .approved_enclaves
+            .get(&code_hash)
+            .expect("Attestation not approved for code hash");
+        let mr_enclave = mr_enclave.to_ascii_lowercase();
+        let count = policy.measurements.len();
+        policy
+            .measurements
+            .retain(|approved| *approved != mr_enclave);
+        assert!(
+            policy.measurements.len() < count,
+            "Measurement not approved"
+        );
+        self.approved_enclaves.insert(&code_hash, &policy);
+        OracleEvent::EnclaveMeasurementRemoved {
+            code_hash: code_hash.clone(),
+            mr_enclave: mr_enclave.clone(),
+        }
+        .emit();
+        self.deauthorize_nodes(&code_hash, Some(&mr_enclave));
+    }
+
+    fn internal_set_signer_policy(&mut self, code_hash: String, signer: SignerPolicy) {
+        let signer = SignerPolicy {
+            mr_signer: signer.mr_signer.map(|value| value.to_ascii_lowercase()),
+            ..signer
+        };
+        let mut policy = self.approved_enclaves.get(&code_hash).unwrap_or_default();
+        policy.signer = signer.clone();
+        self.approved_enclaves.insert(&code_hash, &policy);
+        OracleEvent::SignerPolicySet {
+            code_hash,
+            policy: signer,
+        }
+        .emit();
+    }
+
+    fn internal_approve_root_certificate(&mut self, cert: &[u8]) {
+        attestation::parse_certificate(cert).unwrap_or_else(|err| env::panic_str(&err));
+        let cert_hash = env::sha256_array(cert);
+        self.approved_root_certs.insert(&cert_hash);
+        OracleEvent::RootCertificateApproved {
+            cert_hash: cert_hash.into(),
+        }
+        .emit();
+    }
+
+    fn internal_remove_root_certificate(&mut self, cert_hash: Base58CryptoHash) {
+        assert!(
+            self.approved_root_certs.remove(&cert_hash.into()),
+            "Root certificate not found"
+        );
+        OracleEvent::RootCertificateRemoved { cert_hash }.emit();
+    }
+
+    fn internal_set_attestation_collateral(&mut self, collateral: AttestationCollateral) {
+        self.attestation_collateral = collateral.clone();
+        OracleEvent::AttestationCollateralSet { collateral }.emit();
+    }
+
+    // Deploys the code and chains `migrate` with all remaining gas
+    fn internal_upgrade_contract(&mut self, code: Vec<u8>) {
+        assert!(!code.is_empty(), "Contract code is empty");
+        OracleEvent::ContractUpgradeScheduled {
+            code_hash: code_hash_of(&code),
+        }
+        .emit();
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call_weight(
+                "migrate".to_string(),
+                Vec::new(),
+                NearToken::from_yoctonear(0),
+                Gas::from_gas(0),
+                GasWeight(1),
+            );
     }
 
     fn internal_pause(&mut self) {
         self.paused = true;
-        env::log_str("Oracle paused");
+        OracleEvent::Paused.emit();
     }
 
     fn internal_resume(&mut self) {
         self.paused = false;
-        env::log_str("Oracle resumed");
+        OracleEvent::Resumed.emit();
     }
 
     fn internal_update_config(
         &mut self,
         recency_threshold: Option<u64>,
         min_report_count: Option<u8>,
+        history_depth: Option<u32>,
+        ema_period: Option<u64>,
+        round_duration: Option<u64>,
     ) {
         if let Some(threshold) = recency_threshold {
             self.recency_threshold = threshold;
@@ -723,278 +2410,3224 @@ impl Oracle {
         if let Some(count) = min_report_count {
             self.min_report_count = count.max(1);
         }
-        env::log_str("Configuration updated");
+        if let Some(depth) = history_depth {
+            assert!(
+                (1..=MAX_HISTORY_DEPTH).contains(&depth),
+                "History depth out of range"
+            );
+            self.history_depth = depth;
+        }
+        if let Some(period) = ema_period {
+            assert!(period > 0, "EMA period must be positive");
+            self.ema_period = period;
+        }
+        if let Some(duration) = round_duration {
+            self.round_duration = duration;
+        }
+        OracleEvent::ConfigUpdated {
+            recency_threshold: self.recency_threshold,
+            min_report_count: self.min_report_count,
+            history_depth: self.history_depth,
+            ema_period: self.ema_period,
+            round_duration: self.round_duration,
+        }
+        .emit();
     }
 
-    fn assert_owner(&self) {
+    fn internal_add_asset(&mut self, asset: Asset) {
+        assert!(self.assets.get(&asset.id).is_none(), "Asset already exists");
+        assert_valid_aggregation(&asset.aggregation);
+        let asset_id = asset.id.clone();
+        self.assets.insert(&asset_id, &asset);
+        self.asset_list.push(&asset_id);
+        OracleEvent::AssetAdded { asset_id }.emit();
+    }
+
+    // Replaces an asset's configuration. Decimals are fixed for the lifetime
+    // of an asset since stored reports and history are scaled by them.
+    fn internal_update_asset(&mut self, mut asset: Asset) {
+        let existing = self.assets.get(&asset.id).expect("Asset not found");
         assert_eq!(
-            env::predecessor_account_id(),
-            self.owner,
-            "Only owner can call this"
+            existing.decimals, asset.decimals,
+            "Asset decimals cannot be changed"
         );
+        assert_valid_aggregation(&asset.aggregation);
+
+        let status = std::mem::replace(&mut asset.status, existing.status.clone());
+        self.assets.insert(&asset.id, &asset);
+        OracleEvent::AssetUpdated {
+            asset_id: asset.id.clone(),
+        }
+        .emit();
+        if status != existing.status {
+            self.internal_set_asset_status(&asset.id, status);
+        }
     }
 
-    fn update_aggregated_price(&mut self, asset_id: &str, reports: &[PriceReport]) {
-        if reports.is_empty() {
-            return;
+    // Reversible halt; delisting is an explicit `set_asset_status`
+    fn internal_deactivate_asset(&mut self, asset_id: &String) {
+        self.internal_set_asset_status(asset_id, AssetStatus::QueryFrozen);
+    }
+
+    // Moving out of a reporting status drops the open round; the last
+    // aggregate and history are kept.
+    fn internal_set_asset_status(&mut self, asset_id: &String, status: AssetStatus) {
+        let mut asset = self.assets.get(asset_id).expect("Asset not found");
+        assert!(
+            asset.status != AssetStatus::Delisted || status == AssetStatus::Delisted,
+            "Delisted assets cannot be relisted"
+        );
+
+        if !status.accepts_reports() {
+            if let Some(round) = self.open_rounds.remove(asset_id) {
+                OracleEvent::RoundExpired {
+                    asset_id: asset_id.clone(),
+                    round_id: round.id,
+                    reports: round.reports.len() as u32,
+                }
+                .emit();
+            }
         }
 
-        // Calculate median price
-        let mut prices: Vec<u128> = reports.iter().map(|r| r.price.multiplier).collect();
-        prices.sort();
+        asset.status = status.clone();
+        self.assets.insert(asset_id, &asset);
+        OracleEvent::AssetStatusChanged {
+            asset_id: asset_id.clone(),
+            status,
+        }
+        .emit();
+    }
+
+    // Deletes the asset together with its reports, rounds, prices and history
+    fn internal_remove_asset(&mut self, asset_id: &String) {
+        self.assets.remove(asset_id).expect("Asset not found");
+        if let Some(index) = self.asset_list.iter().position(|id| &id == asset_id) {
+            self.asset_list.swap_remove(index as u64);
+        }
+
+        self.price_reports.remove(asset_id);
+        self.aggregated_prices.remove(asset_id);
+        self.asset_nodes.remove(asset_id);
+        self.drop_price_subscriptions(asset_id);
+        self.open_rounds.remove(asset_id);
+        self.round_counters.remove(asset_id);
+        self.twap_samples.remove(asset_id);
+        self.price_accumulators.remove(asset_id);
+        if let Some(cursor) = self.history_cursors.remove(asset_id) {
+            for seq in cursor.oldest..cursor.next {
+                self.price_history.remove(&(asset_id.clone(), seq));
+            }
+        }
+
+        OracleEvent::AssetRemoved {
+            asset_id: asset_id.clone(),
+        }
+        .emit();
+    }
+
+    fn internal_set_aggregation_method(&mut self, asset_id: &String, method: AggregationMethod) {
+        assert_valid_aggregation(&method);
+        let mut asset = self.assets.get(asset_id).expect("Asset not found");
+        asset.aggregation = method.clone();
+        self.assets.insert(asset_id, &asset);
+        OracleEvent::AggregationMethodUpdated {
+            asset_id: asset_id.clone(),
+            method,
+        }
+        .emit();
+    }
 
-        let median = if prices.len() % 2 == 0 {
-            (prices[prices.len() / 2 - 1] + prices[prices.len() / 2]) / 2
+    fn internal_set_asset_nodes(&mut self, asset_id: &String, mut node_accounts: Vec<AccountId>) {
+        let asset = self.assets.get(asset_id).expect("Asset not found");
+        node_accounts.sort();
+        node_accounts.dedup();
+        if node_accounts.is_empty() {
+            self.asset_nodes.remove(asset_id);
         } else {
-            prices[prices.len() / 2]
-        };
+            for account_id in &node_accounts {
+                assert!(
+                    self.node_to_operator.get(account_id).is_some(),
+                    "Node account not set by any operator"
+                );
+            }
+            assert!(
+                node_accounts.len() >= self.required_sources(&asset),
+                "Fewer assigned nodes than required sources"
+            );
+            self.asset_nodes.insert(asset_id, &node_accounts);
+        }
+        OracleEvent::AssetNodesSet {
+            asset_id: asset_id.clone(),
+            node_accounts,
+        }
+        .emit();
+    }
 
-        // Get the most common decimals value
-        let decimals = reports[0].price.decimals;
+    fn internal_set_node_weight(&mut self, account_id: &AccountId, weight: u32) {
+        let mut node = self.node_details.get(account_id).expect("Node not found");
+        node.weight = weight;
+        self.node_details.insert(account_id, &node);
+        OracleEvent::NodeWeightSet {
+            node_account: account_id.clone(),
+            weight,
+        }
+        .emit();
+    }
+
+    fn internal_report_price(
+        &mut self,
+        node_account: &AccountId,
+        asset_id: String,
+        multiplier: u128,
+        decimals: u8,
+        timestamp: u64,
+    ) -> Result<(), String> {
+        // Verify asset exists and decimals align
+        let asset = self
+            .assets
+            .get(&asset_id)
+            .ok_or_else(|| "Asset not found".to_string())?;
+        if !asset.status.accepts_reports() {
+            return Err("Asset is not accepting reports".to_string());
+        }
+        if asset.decimals != decimals {
+            return Err("Decimals mismatch with asset definition".to_string());
+        }
+        if !self.is_assigned(&asset_id, node_account) {
+            return Err("Node not assigned to asset".to_string());
+        }
 
-        // Get the latest timestamp
-        let timestamp = reports.iter().map(|r| r.timestamp).max().unwrap_or(0);
+        let mut round = self.current_round(&asset_id, &asset, timestamp);
 
-        let aggregated = Price {
-            multiplier: median,
-            decimals,
+        // Replace existing report from this oracle
+        round.reports.retain(|r| &r.oracle_id != node_account);
+        round.reports.push(PriceReport {
+            oracle_id: node_account.clone(),
+            price: Price {
+                multiplier,
+                decimals,
+                timestamp,
+                conf: 0,
+                round_id: round.id,
+            },
             timestamp,
-        };
+            flagged: false,
+        });
+
+        if self.round_has_quorum(&asset, &round)
+            || round.reports.len() >= self.active_node_count(&asset_id)
+        {
+            self.close_round(&asset_id, &asset, round);
+        } else {
+            self.open_rounds.insert(&asset_id, &round);
+        }
 
-        self.aggregated_prices
-            .insert(&asset_id.to_string(), &aggregated);
+        OracleEvent::PriceReported {
+            asset_id,
+            node_account: node_account.clone(),
+            multiplier: U128(multiplier),
+            decimals,
+        }
+        .emit();
+        Ok(())
     }
 
-    fn finalize_reports(&mut self, asset_id: String, asset: &Asset, mut reports: Vec<PriceReport>) {
-        if self.recency_threshold > 0 {
-            let minimum_timestamp = env::block_timestamp().saturating_sub(self.recency_threshold);
-            reports.retain(|r| r.timestamp >= minimum_timestamp);
+    // Items are validated independently; rejected items are returned with a
+    // reason instead of failing the batch.
+    fn internal_report_prices(
+        &mut self,
+        node_account: &AccountId,
+        prices: Vec<PriceInput>,
+        timestamp: u64,
+    ) -> BatchReportResult {
+        let mut result = BatchReportResult::default();
+        for input in prices {
+            if result.accepted.contains(&input.asset_id) {
+                result.rejected.push(RejectedPrice {
+                    asset_id: input.asset_id,
+                    reason: "Duplicate asset in batch".to_string(),
+                });
+                continue;
+            }
+
+            match self.internal_report_price(
+                node_account,
+                input.asset_id.clone(),
+                input.multiplier,
+                input.decimals,
+                timestamp,
+            ) {
+                Ok(()) => result.accepted.push(input.asset_id),
+                Err(reason) => result.rejected.push(RejectedPrice {
+                    asset_id: input.asset_id,
+                    reason,
+                }),
+            }
         }
 
-        if reports.is_empty() {
-            self.price_reports.remove(&asset_id);
-            self.aggregated_prices.remove(&asset_id);
-            env::log_str(&format!("No fresh reports for {}", asset_id));
-            return;
+        if !result.accepted.is_empty() {
+            self.touch_node(node_account, timestamp);
+        }
+        result
+    }
+
+    // Returns the asset's open round, first closing it if its deadline has
+    // passed, or opens the next one.
+    fn current_round(&mut self, asset_id: &String, asset: &Asset, now: u64) -> PriceRound {
+        if let Some(round) = self.open_rounds.get(asset_id) {
+            if now <= round.deadline {
+                return round;
+            }
+            self.close_round(asset_id, asset, round);
+        }
+
+        let round_id = self.round_counters.get(asset_id).unwrap_or(0) + 1;
+        self.round_counters.insert(asset_id, &round_id);
+
+        let round = PriceRound {
+            id: round_id,
+            opened_at: now,
+            deadline: now.saturating_add(self.round_duration),
+            reports: Vec::new(),
+        };
+        OracleEvent::RoundOpened {
+            asset_id: asset_id.clone(),
+            round_id: round.id,
+            deadline: round.deadline,
+        }
+        .emit();
+        round
+    }
+
+    // Aggregates a closed round if it gathered enough reports. Otherwise the
+    // round is dropped and the previous aggregate stays until it goes stale.
+    fn close_round(&mut self, asset_id: &String, asset: &Asset, round: PriceRound) {
+        self.open_rounds.remove(asset_id);
+        if round.reports.len() >= self.required_sources(asset) {
+            let accepted = self.finalize_reports(asset_id.clone(), asset, round.reports.clone());
+            self.credit_reward_points(&accepted);
+            let aggregate = self
+                .aggregated_prices
+                .get(asset_id)
+                .filter(|price| price.round_id == round.id);
+            self.record_reputation(asset_id, &round, aggregate.as_ref());
+        } else {
+            self.record_reputation(asset_id, &round, None);
+            OracleEvent::RoundExpired {
+                asset_id: asset_id.clone(),
+                round_id: round.id,
+                reports: round.reports.len() as u32,
+            }
+            .emit();
+        }
+    }
+
+    // Updates every active eligible node's track record for a closed round.
+    // Deviation is only measured when the round produced an aggregate.
+    fn record_reputation(&mut self, asset_id: &str, round: &PriceRound, aggregate: Option<&Price>) {
+        for account_id in self.eligible_nodes(asset_id) {
+            if !self.is_active_node(&account_id) {
+                continue;
+            }
+            let Some(mut node) = self.node_details.get(&account_id) else {
+                continue;
+            };
+            let report = round.reports.iter().find(|r| r.oracle_id == account_id);
+            let latency = report.map(|r| r.timestamp.saturating_sub(round.opened_at));
+            let deviation = report.zip(aggregate).map(|(r, price)| {
+                deviation_bps(r.price.multiplier, price.multiplier).min(u32::MAX as u128) as u32
+            });
+            node.reputation
+                .record(latency, deviation, self.round_duration);
+            self.node_details.insert(&account_id, &node);
         }
+    }
+
+    // Asset whose prices may be served to consumers
+    fn queryable_asset(&self, asset_id: &String) -> Option<Asset> {
+        self.assets
+            .get(asset_id)
+            .filter(|asset| asset.status.serves_queries())
+    }
 
-        let required_sources = std::cmp::max(
+    fn required_sources(&self, asset: &Asset) -> usize {
+        std::cmp::max(
             std::cmp::max(self.min_report_count as usize, asset.min_sources as usize),
             1,
+        )
+    }
+
+    // Panics unless the caller is an authorized, unsuspended node. A node whose
+    // suspension has run out is reinstated by its next report.
+    fn assert_reporting_node(&mut self, node_account: &AccountId, now: u64) {
+        assert!(
+            self.authorized_nodes.contains(node_account),
+            "Not an authorized node"
         );
+        let node = self.node_details.get(node_account).expect("Node not found");
+        assert!(
+            !self.attestation_lapsed(&node, now),
+            "Node attestation has lapsed"
+        );
+        assert!(self.is_bonded(node_account), "Operator bond below minimum");
+        if !node.active {
+            assert!(now >= node.suspended_until, "Node is suspended");
+            self.internal_reinstate_node(node_account);
+        }
+    }
 
-        if reports.len() < required_sources {
-            self.price_reports.insert(&asset_id, &reports);
-            self.aggregated_prices.remove(&asset_id);
-            env::log_str(&format!(
-                "Insufficient fresh reports for {} (have {}, need {})",
-                asset_id,
-                reports.len(),
-                required_sources
-            ));
-        } else {
-            self.price_reports.insert(&asset_id, &reports);
-            self.update_aggregated_price(&asset_id, &reports);
+    // Nodes with a lapsed attestation or an underbonded operator count as
+    // inactive until that is fixed
+    fn is_active_node(&self, account_id: &AccountId) -> bool {
+        if !self.authorized_nodes.contains(account_id) || !self.is_bonded(account_id) {
+            return false;
         }
+        self.node_details.get(account_id).is_some_and(|node| {
+            node.active && !self.attestation_lapsed(&node, env::block_timestamp())
+        })
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
-    use near_sdk::testing_env;
+    fn attestation_expiry(&self, node: &OracleNode) -> u64 {
+        if self.reattestation_window == 0 {
+            return 0;
+        }
+        node.attested_at.saturating_add(self.reattestation_window)
+    }
 
-    fn get_context(predecessor: AccountId) -> VMContextBuilder {
-        let mut builder = VMContextBuilder::new();
-        builder.predecessor_account_id(predecessor);
-        builder
+    fn attestation_lapsed(&self, node: &OracleNode, now: u64) -> bool {
+        self.reattestation_window > 0 && now > self.attestation_expiry(node)
+    }
+
+    // Whether the round holds the asset's quorum of reports: one per operator
+    // from active nodes, within the asset's deviation band around their median.
+    fn round_has_quorum(&self, asset: &Asset, round: &PriceRound) -> bool {
+        let reports: Vec<PriceReport> = round
+            .reports
+            .iter()
+            .filter(|r| self.is_active_node(&r.oracle_id))
+            .cloned()
+            .collect();
+        let reports = self.latest_per_operator(&reports);
+        let mut accepted = reports.len();
+        if asset.max_deviation_bps > 0 {
+            let mut prices: Vec<u128> = reports.iter().map(|r| r.price.multiplier).collect();
+            let provisional = median_of(&mut prices);
+            accepted = reports
+                .iter()
+                .filter(|r| {
+                    deviation_bps(r.price.multiplier, provisional)
+                        <= asset.max_deviation_bps as u128
+                })
+                .count();
+        }
+        accepted
+            >= self
+                .required_sources(asset)
+                .max(asset.round_quorum as usize)
+    }
+
+    // Unassigned nodes don't hold the asset's rounds open
+    fn active_node_count(&self, asset_id: &str) -> usize {
+        self.eligible_nodes(asset_id)
+            .iter()
+            .filter(|account_id| self.is_active_node(account_id))
+            .count()
+    }
+
+    fn is_assigned(&self, asset_id: &str, account_id: &AccountId) -> bool {
+        self.asset_nodes
+            .get(&asset_id.to_string())
+            .is_none_or(|nodes| nodes.contains(account_id))
+    }
+
+    fn eligible_nodes(&self, asset_id: &str) -> Vec<AccountId> {
+        match self.asset_nodes.get(&asset_id.to_string()) {
+            Some(nodes) => nodes
+                .into_iter()
+                .filter(|account_id| self.authorized_nodes.contains(account_id))
+                .collect(),
+            None => self.authorized_nodes.iter().collect(),
+        }
+    }
+
+    fn touch_node(&mut self, node_account: &AccountId, timestamp: u64) {
+        if let Some(mut node) = self.node_details.get(node_account) {
+            node.last_report = timestamp;
+            self.node_details.insert(node_account, &node);
+        }
+    }
+
+    fn assert_owner(&self) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner,
+            "Only owner can call this"
+        );
+    }
+
+    // Counts a flagged report against the node and suspends it once its streak
+    // reaches the jail policy limit. Its flagged report is already excluded, so
+    // nothing needs purging here.
+    fn record_outlier(&mut self, node_account: &AccountId) {
+        let mut node = match self.node_details.get(node_account) {
+            Some(node) => node,
+            None => return,
+        };
+        node.outlier_reports = node.outlier_reports.saturating_add(1);
+        node.consecutive_outliers = node.consecutive_outliers.saturating_add(1);
+
+        let limit = self.jail_policy.max_consecutive_outliers;
+        if node.active && limit > 0 && node.consecutive_outliers >= limit {
+            let until = env::block_timestamp().saturating_add(self.jail_policy.jail_duration);
+            self.jail_node(node, until, SuspensionReason::RepeatedOutliers);
+        } else {
+            self.node_details.insert(node_account, &node);
+        }
+    }
+
+    fn clear_outlier_streak(&mut self, node_account: &AccountId) {
+        if let Some(mut node) = self.node_details.get(node_account) {
+            if node.consecutive_outliers > 0 {
+                node.consecutive_outliers = 0;
+                self.node_details.insert(node_account, &node);
+            }
+        }
+    }
+
+    fn update_aggregated_price(&mut self, asset: &Asset, reports: &[PriceReport]) -> Option<Price> {
+        if reports.is_empty() {
+            return None;
+        }
+
+        let mut prices: Vec<u128> = reports.iter().map(|r| r.price.multiplier).collect();
+
+        // Get the most common decimals value
+        let decimals = reports[0].price.decimals;
+
+        // Get the latest timestamp. Re-aggregating older reports, e.g. after a
+        // node is removed, corrects the price in force rather than publishing
+        // one back in time, so history and accumulators stay ordered.
+        let published = self
+            .price_accumulators
+            .get(&asset.id)
+            .map_or(0, |accumulator| accumulator.last_timestamp);
+        let timestamp = reports
+            .iter()
+            .map(|r| r.timestamp)
+            .max()
+            .unwrap_or(0)
+            .max(published);
+
+        let round_id = reports[0].price.round_id;
+
+        let value = match &asset.aggregation {
+            AggregationMethod::Median => median_of(&mut prices),
+            AggregationMethod::TrimmedMean { trim_bps } => trimmed_mean_of(&mut prices, *trim_bps),
+            AggregationMethod::WeightedMedian => {
+                let mut weighted: Vec<(u128, u128)> = reports
+                    .iter()
+                    .map(|r| {
+                        let weight = self
+                            .node_details
+                            .get(&r.oracle_id)
+                            .map(|node| node.weight)
+                            .unwrap_or(1);
+                        (r.price.multiplier, weight as u128)
+                    })
+                    .collect();
+                weighted_median_of(&mut weighted)
+            }
+            AggregationMethod::ReputationWeightedMedian => {
+                let mut weighted: Vec<(u128, u128)> = reports
+                    .iter()
+                    .map(|r| {
+                        let weight = self
+                            .node_details
+                            .get(&r.oracle_id)
+                            .map(|node| node.weight as u128 * (1 + node.reputation.score as u128))
+                            .unwrap_or(1);
+                        (r.price.multiplier, weight)
+                    })
+                    .collect();
+                weighted_median_of(&mut weighted)
+            }
+            AggregationMethod::StakeWeightedMedian => {
+                let mut weighted: Vec<(u128, u128)> = reports
+                    .iter()
+                    .map(|r| {
+                        let weight = self
+                            .node_details
+                            .get(&r.oracle_id)
+                            .map_or(1, |node| node.weight as u128);
+                        let stake = self.bonded_amount(&r.oracle_id);
+                        (r.price.multiplier, weight.saturating_mul(stake))
+                    })
+                    .collect();
+                weighted_median_of(&mut weighted)
+            }
+            AggregationMethod::Twap { window } => {
+                let spot = median_of(&mut prices);
+                self.record_twap_sample(&asset.id, spot, decimals, timestamp, *window)
+            }
+        };
+
+        // Confidence is the median absolute deviation from the published value
+        let mut deviations: Vec<u128> = prices.iter().map(|p| p.abs_diff(value)).collect();
+        let conf = median_of(&mut deviations);
+
+        let aggregated = Price {
+            multiplier: value,
+            decimals,
+            timestamp,
+            conf,
+            round_id,
+        };
+
+        self.aggregated_prices.insert(&asset.id, &aggregated);
+        Some(aggregated)
+    }
+
+    // Folds the previous aggregate into the accumulator up to `price.timestamp`
+    // and makes `price` the one in force. Returns the cumulative value at that time.
+    fn accumulate_price(&mut self, asset_id: &String, price: &Price) -> u128 {
+        let accumulator = match self.price_accumulators.get(asset_id) {
+            Some(previous) => {
+                let elapsed = price.timestamp.saturating_sub(previous.last_timestamp);
+                let ema = self.ema_at(&previous, price.timestamp);
+                PriceAccumulator {
+                    cumulative: previous
+                        .cumulative
+                        .saturating_add(previous.last_price.saturating_mul(elapsed as u128)),
+                    last_price: price.multiplier,
+                    last_conf: price.conf,
+                    last_timestamp: price.timestamp.max(previous.last_timestamp),
+                    decimals: price.decimals,
+                    ema_price: ema.multiplier,
+                    ema_conf: ema.conf,
+                }
+            }
+            None => PriceAccumulator {
+                cumulative: 0,
+                last_price: price.multiplier,
+                last_conf: price.conf,
+                last_timestamp: price.timestamp,
+                decimals: price.decimals,
+                ema_price: price.multiplier,
+                ema_conf: price.conf,
+            },
+        };
+
+        self.price_accumulators.insert(asset_id, &accumulator);
+        accumulator.cumulative
+    }
+
+    // EMA at `timestamp`, moving towards the last aggregate for the time it was in force
+    fn ema_at(&self, accumulator: &PriceAccumulator, timestamp: u64) -> Price {
+        let elapsed = timestamp.saturating_sub(accumulator.last_timestamp);
+        Price {
+            multiplier: ema_step(
+                accumulator.ema_price,
+                accumulator.last_price,
+                elapsed,
+                self.ema_period,
+            ),
+            decimals: accumulator.decimals,
+            timestamp: accumulator.last_timestamp,
+            conf: ema_step(
+                accumulator.ema_conf,
+                accumulator.last_conf,
+                elapsed,
+                self.ema_period,
+            ),
+            round_id: 0,
+        }
+    }
+
+    // Last history entry published at or before `timestamp`
+    fn history_entry_at(&self, asset_id: &String, timestamp: u64) -> Option<HistoryEntry> {
+        let cursor = self.history_cursors.get(asset_id)?;
+
+        let (mut low, mut high) = (cursor.oldest, cursor.next);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let entry = self.price_history.get(&(asset_id.clone(), mid))?;
+            if entry.price.timestamp <= timestamp {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        if low == cursor.oldest {
+            return None;
+        }
+        self.price_history.get(&(asset_id.clone(), low - 1))
+    }
+
+    // Appends to the asset's history, replacing the last entry when the price
+    // is not newer (same block or a re-aggregation), and drops the oldest
+    // entries beyond `history_depth`, a few at a time.
+    fn record_history(&mut self, asset_id: &String, price: &Price, cumulative: u128) {
+        let mut cursor = self.history_cursors.get(asset_id).unwrap_or_default();
+        let entry = HistoryEntry {
+            price: price.clone(),
+            cumulative,
+        };
+
+        let replaces_last = cursor.next > cursor.oldest
+            && self
+                .price_history
+                .get(&(asset_id.clone(), cursor.next - 1))
+                .is_some_and(|last| last.price.timestamp >= price.timestamp);
+        if replaces_last {
+            self.price_history
+                .insert(&(asset_id.clone(), cursor.next - 1), &entry);
+        } else {
+            self.price_history
+                .insert(&(asset_id.clone(), cursor.next), &entry);
+            cursor.next += 1;
+        }
+
+        let mut trimmed = 0;
+        while cursor.next - cursor.oldest > self.history_depth as u64
+            && trimmed < HISTORY_TRIM_PER_UPDATE
+        {
+            trimmed += 1;
+            self.price_history
+                .remove(&(asset_id.clone(), cursor.oldest));
+            cursor.oldest += 1;
+        }
+
+        self.history_cursors.insert(asset_id, &cursor);
+    }
+
+    // Stores the latest spot median and returns its time-weighted average over
+    // the window. Each sample holds until the next one; the newest until now.
+    fn record_twap_sample(
+        &mut self,
+        asset_id: &String,
+        spot: u128,
+        decimals: u8,
+        timestamp: u64,
+        window: u64,
+    ) -> u128 {
+        let now = env::block_timestamp();
+        let window_start = now.saturating_sub(window);
+
+        let mut samples = self.twap_samples.get(asset_id).unwrap_or_default();
+        samples.retain(|sample| sample.timestamp != timestamp);
+        samples.push(Price {
+            multiplier: spot,
+            decimals,
+            timestamp,
+            conf: 0,
+            round_id: 0,
+        });
+
+        // Keep the last sample before the window, it is still in force at its start
+        let first_in_window = samples
+            .iter()
+            .position(|sample| sample.timestamp >= window_start)
+            .unwrap_or(samples.len() - 1);
+        samples.drain(..first_in_window.saturating_sub(1));
+
+        let mut weighted_sum: u128 = 0;
+        let mut total_time: u128 = 0;
+        for (i, sample) in samples.iter().enumerate() {
+            let start = sample.timestamp.max(window_start);
+            let end = samples.get(i + 1).map_or(now, |next| next.timestamp);
+            if end > start {
+                let duration = (end - start) as u128;
+                weighted_sum =
+                    weighted_sum.saturating_add(sample.multiplier.saturating_mul(duration));
+                total_time += duration;
+            }
+        }
+
+        self.twap_samples.insert(asset_id, &samples);
+
+        if total_time == 0 {
+            spot
+        } else {
+            weighted_sum / total_time
+        }
+    }
+
+    // Returns the reports behind a new aggregate, or nothing when none was
+    // produced. Without a new aggregate, the previous one and the reports
+    // behind it are left in place until they go stale.
+    fn finalize_reports(
+        &mut self,
+        asset_id: String,
+        asset: &Asset,
+        mut reports: Vec<PriceReport>,
+    ) -> Vec<PriceReport> {
+        // Halted assets keep their last aggregate untouched
+        if !asset.status.accepts_reports() {
+            self.price_reports.insert(&asset_id, &reports);
+            return Vec::new();
+        }
+        reports.retain(|r| {
+            self.is_active_node(&r.oracle_id) && self.is_assigned(&asset_id, &r.oracle_id)
+        });
+
+        if self.recency_threshold > 0 {
+            let minimum_timestamp = env::block_timestamp().saturating_sub(self.recency_threshold);
+            reports.retain(|r| r.timestamp >= minimum_timestamp);
+        }
+
+        if reports.is_empty() {
+            OracleEvent::PriceUnavailable {
+                asset_id,
+                fresh_reports: 0,
+                required_sources: 0,
+            }
+            .emit();
+            return Vec::new();
+        }
+
+        if asset.max_deviation_bps > 0 {
+            self.flag_outliers(&asset_id, asset.max_deviation_bps, &mut reports);
+        }
+        let accepted: Vec<PriceReport> = reports.iter().filter(|r| !r.flagged).cloned().collect();
+        let accepted = self.latest_per_operator(&accepted);
+
+        let required_sources = self.required_sources(asset);
+
+        if accepted.len() < required_sources {
+            OracleEvent::PriceUnavailable {
+                asset_id,
+                fresh_reports: accepted.len() as u32,
+                required_sources: required_sources as u32,
+            }
+            .emit();
+            Vec::new()
+        } else {
+            self.price_reports.insert(&asset_id, &reports);
+            if let Some(aggregated) = self.update_aggregated_price(asset, &accepted) {
+                let cumulative = self.accumulate_price(&asset_id, &aggregated);
+                self.record_history(&asset_id, &aggregated, cumulative);
+                OracleEvent::PriceUpdated {
+                    asset_id: asset_id.clone(),
+                    round_id: aggregated.round_id,
+                    multiplier: U128(aggregated.multiplier),
+                    decimals: aggregated.decimals,
+                    conf: U128(aggregated.conf),
+                    timestamp: aggregated.timestamp,
+                    num_sources: accepted.len() as u8,
+                }
+                .emit();
+                if !self.updated_assets.contains(&asset_id) {
+                    self.updated_assets.push(asset_id);
+                }
+                return accepted;
+            }
+            Vec::new()
+        }
+    }
+
+    // Keeps each operator's most recent report, so redundant nodes run by one
+    // operator count as a single source.
+    fn latest_per_operator(&self, reports: &[PriceReport]) -> Vec<PriceReport> {
+        let mut latest: Vec<(AccountId, PriceReport)> = Vec::new();
+        for report in reports {
+            let operator = self
+                .node_to_operator
+                .get(&report.oracle_id)
+                .unwrap_or_else(|| report.oracle_id.clone());
+            match latest.iter_mut().find(|(id, _)| *id == operator) {
+                Some((_, kept)) if kept.timestamp >= report.timestamp => {}
+                Some((_, kept)) => *kept = report.clone(),
+                None => latest.push((operator, report.clone())),
+            }
+        }
+        latest.into_iter().map(|(_, report)| report).collect()
+    }
+
+    // Flags reports too far from the provisional median of fresh reports, one
+    // per operator.
+    // A node is only charged once per report, when it first becomes flagged.
+    fn flag_outliers(
+        &mut self,
+        asset_id: &str,
+        max_deviation_bps: u16,
+        reports: &mut [PriceReport],
+    ) {
+        let mut prices: Vec<u128> = self
+            .latest_per_operator(reports)
+            .iter()
+            .map(|r| r.price.multiplier)
+            .collect();
+        let provisional = median_of(&mut prices);
+
+        for report in reports.iter_mut() {
+            let flagged =
+                deviation_bps(report.price.multiplier, provisional) > max_deviation_bps as u128;
+            if flagged && !report.flagged {
+                OracleEvent::OutlierFlagged {
+                    asset_id: asset_id.to_string(),
+                    node_account: report.oracle_id.clone(),
+                    multiplier: U128(report.price.multiplier),
+                }
+                .emit();
+                self.record_outlier(&report.oracle_id);
+            } else if !flagged {
+                self.clear_outlier_streak(&report.oracle_id);
+            }
+            report.flagged = flagged;
+        }
+    }
+}
+
+fn code_hash_of(code: &[u8]) -> Base58CryptoHash {
+    let hash: CryptoHash = env::sha256_array(code);
+    hash.into()
+}
+
+fn assert_valid_aggregation(method: &AggregationMethod) {
+    match method {
+        AggregationMethod::TrimmedMean { trim_bps } => {
+            assert!(*trim_bps < 5_000, "Trim must be below 5000 basis points");
+        }
+        AggregationMethod::Twap { window } => {
+            assert!(*window > 0, "TWAP window must be positive");
+        }
+        AggregationMethod::Median
+        | AggregationMethod::WeightedMedian
+        | AggregationMethod::ReputationWeightedMedian
+        | AggregationMethod::StakeWeightedMedian => {}
+    }
+}
+
+fn deviation_bps(value: u128, reference: u128) -> u128 {
+    if reference == 0 {
+        return if value == 0 { 0 } else { u128::MAX };
+    }
+    value.abs_diff(reference).saturating_mul(10_000) / reference
+}
+
+// Moves `ema` towards `price` by elapsed / (elapsed + period), a rational
+// approximation of 1 - exp(-elapsed / period)
+fn ema_step(ema: u128, price: u128, elapsed: u64, period: u64) -> u128 {
+    let denominator = elapsed as u128 + period as u128;
+    if denominator == 0 {
+        return price;
+    }
+    let delta = ema.abs_diff(price).saturating_mul(elapsed as u128) / denominator;
+    if price >= ema {
+        ema + delta
+    } else {
+        ema - delta
+    }
+}
+
+fn median_of(values: &mut [u128]) -> u128 {
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2
+    } else {
+        values[mid]
+    }
+}
+
+fn trimmed_mean_of(values: &mut [u128], trim_bps: u16) -> u128 {
+    values.sort_unstable();
+    let trim = values.len() * trim_bps as usize / 10_000;
+    let kept = &values[trim..values.len() - trim];
+    if kept.is_empty() {
+        return median_of(values);
+    }
+    kept.iter().fold(0u128, |sum, v| sum.saturating_add(*v)) / kept.len() as u128
+}
+
+// Lowest value at which the cumulative weight reaches half of the total
+fn weighted_median_of(values: &mut [(u128, u128)]) -> u128 {
+    values.sort_unstable_by_key(|(value, _)| *value);
+    let total: u128 = values.iter().map(|(_, weight)| weight).sum();
+    if total == 0 {
+        let mut plain: Vec<u128> = values.iter().map(|(value, _)| *value).collect();
+        return median_of(&mut plain);
+    }
+    let mut cumulative = 0u128;
+    for (value, weight) in values.iter() {
+        cumulative += weight;
+        if cumulative * 2 >= total {
+            return *value;
+        }
+    }
+    values[values.len() - 1].0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn get_context(predecessor: AccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder.predecessor_account_id(predecessor);
+        builder
+    }
+
+    fn node(index: usize) -> AccountId {
+        format!("node{}.near", index).parse().unwrap()
+    }
+
+    fn operator(index: usize) -> AccountId {
+        format!("operator{}.near", index).parse().unwrap()
+    }
+
+    const MR_ENCLAVE: [u8; 32] = [0x11; 32];
+
+    // Trusts the test root certificate and approves MR_ENCLAVE for `code_hash`
+    fn approve_test_enclave(contract: &mut Oracle, code_hash: &str) {
+        contract.approve_root_certificate(attestation::test_utils::root_certificate().into());
+        contract.approve_attestation(code_hash.to_string(), attestation::to_hex(&MR_ENCLAVE));
+    }
+
+    // Quote bound to the current signer key, as produced by the node enclave
+    fn attestation_for(measurement: [u8; 32], issued_at: u64) -> AttestationData {
+        attestation_bound_to(&env::signer_account_pk(), measurement, issued_at)
+    }
+
+    fn attestation_bound_to(
+        key: &PublicKey,
+        measurement: [u8; 32],
+        issued_at: u64,
+    ) -> AttestationData {
+        AttestationData {
+            quote: attestation::test_utils::sgx_quote(measurement, report_data_for(key, issued_at))
+                .into(),
+        }
+    }
+
+    fn report_data_for(key: &PublicKey, issued_at: u64) -> [u8; 64] {
+        let mut report_data = [0u8; 64];
+        report_data[..32].copy_from_slice(&env::sha256(key.as_bytes()));
+        report_data[32..40].copy_from_slice(&issued_at.to_le_bytes());
+        report_data
+    }
+
+    fn near_asset(min_sources: u8) -> Asset {
+        Asset {
+            id: "near".to_string(),
+            symbol: "NEAR".to_string(),
+            name: "NEAR Protocol".to_string(),
+            decimals: 4,
+            status: AssetStatus::Active,
+            min_sources,
+            max_deviation_bps: 0,
+            aggregation: AggregationMethod::Median,
+            round_quorum: 0,
+        }
+    }
+
+    // Owner is accounts(0); node i is run by operator i and attests with "hash1".
+    // Rounds wait for all nodes unless the test lowers `round_quorum`.
+    fn setup_with_nodes(
+        context: &mut VMContextBuilder,
+        node_count: usize,
+        min_sources: u8,
+    ) -> Oracle {
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = Oracle::new(accounts(0), 300_000_000_000, min_sources);
+        contract.add_asset(Asset {
+            round_quorum: node_count as u8,
+            ..near_asset(min_sources)
+        });
+        contract.approve_code_hash("hash1".to_string());
+        approve_test_enclave(&mut contract, "hash1");
+
+        for i in 0..node_count {
+            context.predecessor_account_id(accounts(0));
+            testing_env!(context.build());
+            contract.add_node_operator(operator(i));
+
+            context.predecessor_account_id(operator(i));
+            testing_env!(context.build());
+            contract.set_node_account(node(i));
+
+            context.predecessor_account_id(node(i));
+            testing_env!(context.build());
+            contract.register_node(
+                "hash1".to_string(),
+                attestation_for(MR_ENCLAVE, env::block_timestamp()),
+                None,
+            );
+        }
+        contract
+    }
+
+    fn report_as(
+        context: &mut VMContextBuilder,
+        contract: &mut Oracle,
+        index: usize,
+        multiplier: u128,
+    ) {
+        context.predecessor_account_id(node(index));
+        testing_env!(context.build());
+        contract.report_price("near".to_string(), multiplier, 4);
+    }
+
+    #[test]
+    fn test_initialization() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let contract = Oracle::new(accounts(0), 300_000_000_000, 3);
+        assert_eq!(contract.owner, accounts(0));
+        assert_eq!(contract.recency_threshold, 300_000_000_000);
+        assert_eq!(contract.min_report_count, 3);
+        assert_eq!(contract.attestation_max_age, 600_000_000_000);
+        assert_eq!(contract.admin_timelock_delay, 0);
+        assert_eq!(contract.admin_quorum_bps, 5000);
+        assert_eq!(contract.proposal_counter, 0);
+    }
+
+    #[test]
+    fn test_add_asset() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = Oracle::new(accounts(0), 300_000_000_000, 3);
+
+        contract.add_asset(near_asset(3));
+
+        let assets = contract.get_assets();
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].id, "near");
+    }
+
+    #[test]
+    fn test_node_registration_flow() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = Oracle::new(accounts(0), 300_000_000_000, 3);
+
+        // Add operator
+        contract.add_node_operator(accounts(1));
+
+        // Approve code hash
+        let code_hash = "test_hash_123".to_string();
+        contract.approve_code_hash(code_hash.clone());
+        approve_test_enclave(&mut contract, &code_hash);
+
+        // Operator sets node account
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        contract.set_node_account(accounts(2));
+
+        // Node registers
+        context.predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        contract.register_node(
+            code_hash,
+            attestation_for(MR_ENCLAVE, env::block_timestamp()),
+            None,
+        );
+
+        // Verify node is authorized
+        assert!(contract.is_authorized(accounts(2)));
+    }
+
+    #[test]
+    fn test_price_reporting_and_aggregation() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = Oracle::new(accounts(0), 300_000_000_000, 1);
+
+        // Setup
+        contract.add_asset(near_asset(1));
+
+        // Register two nodes
+        contract.add_node_operator(accounts(1));
+        contract.approve_code_hash("hash1".to_string());
+        approve_test_enclave(&mut contract, "hash1");
+
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        contract.set_node_account(accounts(2));
+
+        context.predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        contract.register_node(
+            "hash1".to_string(),
+            attestation_for(MR_ENCLAVE, env::block_timestamp()),
+            None,
+        );
+
+        // Report price from node 1
+        contract.report_price("near".to_string(), 35000, 4); // $3.5000
+
+        // Check price
+        let price_data = contract.get_price("near".to_string());
+        assert!(price_data.is_some());
+        let price = price_data.unwrap();
+        assert_eq!(price.price.multiplier, 35000);
+        assert_eq!(price.price.decimals, 4);
+    }
+
+    #[test]
+    fn test_price_requires_minimum_sources() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = Oracle::new(accounts(0), 300_000_000_000, 2);
+
+        contract.add_asset(near_asset(2));
+
+        contract.add_node_operator(accounts(1));
+        contract.add_node_operator(accounts(2));
+        contract.approve_code_hash("hash1".to_string());
+        approve_test_enclave(&mut contract, "hash1");
+
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        contract.set_node_account(accounts(3));
+        context.predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        contract.set_node_account(accounts(4));
+
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+        contract.register_node(
+            "hash1".to_string(),
+            attestation_for(MR_ENCLAVE, env::block_timestamp()),
+            None,
+        );
+        context.predecessor_account_id(accounts(4));
+        testing_env!(context.build());
+        contract.register_node(
+            "hash1".to_string(),
+            attestation_for(MR_ENCLAVE, env::block_timestamp()),
+            None,
+        );
+
+        // Only one report -> should not set aggregated price
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+        contract.report_price("near".to_string(), 35000, 4);
+        assert!(contract.get_price("near".to_string()).is_none());
+
+        // Second node reports -> aggregation allowed
+        context.predecessor_account_id(accounts(4));
+        testing_env!(context.build());
+        contract.report_price("near".to_string(), 36000, 4);
+        let price = contract
+            .get_price("near".to_string())
+            .expect("price available");
+        assert_eq!(price.num_sources, 2);
+        assert_eq!(price.price.multiplier, 35500); // median of 35000 and 36000
+    }
+
+    #[test]
+    fn test_aggregated_price_reports_confidence() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 3, 3);
+
+        report_as(&mut context, &mut contract, 0, 35000);
+        report_as(&mut context, &mut contract, 1, 36000);
+        report_as(&mut context, &mut contract, 2, 38000);
+
+        let price = contract
+            .get_price("near".to_string())
+            .expect("price available");
+        assert_eq!(price.price.multiplier, 36000);
+        // Deviations from the median are 1000, 0 and 2000
+        assert_eq!(price.price.conf, 1000);
+
+        let pyth = contract
+            .get_price_no_older_than("near".to_string(), 60_000_000_000)
+            .expect("pyth price available");
+        assert_eq!(pyth.conf, 1000);
+        assert_eq!(pyth.price, 36000);
+    }
+
+    #[test]
+    fn test_outlier_reports_are_excluded_and_flagged() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 3, 2);
+
+        let mut asset = near_asset(2);
+        asset.max_deviation_bps = 500;
+        asset.round_quorum = 3;
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.update_asset(asset);
+
+        report_as(&mut context, &mut contract, 0, 35000);
+        report_as(&mut context, &mut contract, 1, 35500);
+        report_as(&mut context, &mut contract, 2, 70000);
+
+        let price = contract
+            .get_price("near".to_string())
+            .expect("price available");
+        assert_eq!(price.price.multiplier, 35250);
+        assert_eq!(price.num_sources, 2);
+
+        let reports = contract.get_price_reports("near".to_string());
+        let flagged: Vec<_> = reports.iter().filter(|r| r.flagged).collect();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].oracle_id, node(2));
+        assert_eq!(
+            contract.get_node_details(node(2)).unwrap().outlier_reports,
+            1
+        );
+        assert_eq!(
+            contract.get_node_details(node(0)).unwrap().outlier_reports,
+            0
+        );
+    }
+
+    #[test]
+    fn test_aggregation_helpers() {
+        assert_eq!(trimmed_mean_of(&mut [10, 100, 102, 104, 1000], 2000), 102);
+        assert_eq!(trimmed_mean_of(&mut [100, 200], 4000), 150);
+        assert_eq!(weighted_median_of(&mut [(100, 1), (200, 5), (300, 1)]), 200);
+        assert_eq!(weighted_median_of(&mut [(100, 3), (200, 1), (300, 1)]), 100);
+    }
+
+    #[test]
+    fn test_weighted_median_uses_node_weight() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 3, 3);
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_aggregation_method("near".to_string(), AggregationMethod::WeightedMedian);
+        contract.set_node_weight(node(2), 5);
+
+        report_as(&mut context, &mut contract, 0, 35000);
+        report_as(&mut context, &mut contract, 1, 36000);
+        report_as(&mut context, &mut contract, 2, 38000);
+
+        let price = contract
+            .get_price("near".to_string())
+            .expect("price available");
+        assert_eq!(price.price.multiplier, 38000);
+    }
+
+    #[test]
+    fn test_twap_aggregation_smooths_spot_median() {
+        let mut context = get_context(accounts(0));
+        context.block_timestamp(1_000);
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_aggregation_method(
+            "near".to_string(),
+            AggregationMethod::Twap { window: 1_000 },
+        );
+
+        report_as(&mut context, &mut contract, 0, 30000);
+        context.block_timestamp(1_750);
+        report_as(&mut context, &mut contract, 0, 40000);
+        assert_eq!(
+            contract
+                .get_price("near".to_string())
+                .unwrap()
+                .price
+                .multiplier,
+            30000
+        );
+
+        // 750ns at 30000 and 250ns at 40000 within the window
+        context.block_timestamp(2_000);
+        report_as(&mut context, &mut contract, 0, 40000);
+        assert_eq!(
+            contract
+                .get_price("near".to_string())
+                .unwrap()
+                .price
+                .multiplier,
+            32500
+        );
+    }
+
+    #[test]
+    fn test_price_history_queries() {
+        let mut context = get_context(accounts(0));
+        context.block_timestamp(1_000);
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.update_config(None, None, Some(3), None, None);
+
+        for (timestamp, multiplier) in [
+            (1_000, 30000),
+            (2_000, 31000),
+            (3_000, 32000),
+            (4_000, 33000),
+        ] {
+            context.block_timestamp(timestamp);
+            report_as(&mut context, &mut contract, 0, multiplier);
+        }
+        // A second report in the same block replaces that block's entry
+        report_as(&mut context, &mut contract, 0, 33500);
+
+        let history = contract.get_price_history("near".to_string(), 0, 10);
+        let values: Vec<u128> = history.iter().map(|p| p.multiplier).collect();
+        assert_eq!(values, vec![31000, 32000, 33500]);
+
+        let limited = contract.get_price_history("near".to_string(), 3_000, 1);
+        assert_eq!(limited.len(), 1);
+        assert_eq!(limited[0].multiplier, 32000);
+
+        assert_eq!(
+            contract
+                .get_price_at("near".to_string(), 2_500)
+                .unwrap()
+                .multiplier,
+            31000
+        );
+        assert_eq!(
+            contract
+                .get_price_at("near".to_string(), 9_000)
+                .unwrap()
+                .multiplier,
+            33500
+        );
+        // Older than the retained history
+        assert!(contract.get_price_at("near".to_string(), 1_500).is_none());
+
+        // A lower depth is reached over the following updates
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.update_config(None, None, Some(1), None, None);
+        context.block_timestamp(5_000);
+        report_as(&mut context, &mut contract, 0, 34000);
+        let history = contract.get_price_history("near".to_string(), 0, 10);
+        assert_eq!(history.len(), 2);
+        context.block_timestamp(6_000);
+        report_as(&mut context, &mut contract, 0, 35000);
+        let history = contract.get_price_history("near".to_string(), 0, 10);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].multiplier, 35000);
+    }
+
+    #[test]
+    #[should_panic(expected = "History depth out of range")]
+    fn test_history_depth_must_be_positive() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.update_config(None, None, Some(0), None, None);
+    }
+
+    #[test]
+    fn test_twap_and_ema_views() {
+        let mut context = get_context(accounts(0));
+        context.block_timestamp(1_000);
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.update_config(None, None, None, Some(1_000), None);
+
+        report_as(&mut context, &mut contract, 0, 30000);
+        context.block_timestamp(2_000);
+        report_as(&mut context, &mut contract, 0, 40000);
+
+        context.block_timestamp(3_000);
+        testing_env!(context.build());
+        // 1000ns at 30000 then 1000ns at 40000
+        let twap = contract.get_twap("near".to_string(), 2_000).unwrap();
+        assert_eq!(twap.multiplier, 35000);
+        let twap = contract.get_twap("near".to_string(), 500).unwrap();
+        assert_eq!(twap.multiplier, 40000);
+        // Window reaches back before the first recorded price
+        assert!(contract.get_twap("near".to_string(), 2_500).is_none());
+
+        // 30000 was in force until t=2000, then 40000 for one EMA period
+        let ema = contract
+            .get_ema_price_no_older_than("near".to_string(), 5_000)
+            .unwrap();
+        assert_eq!(ema.price, 35000);
+        assert!(contract
+            .get_ema_price_no_older_than("near".to_string(), 500)
+            .is_none());
+    }
+
+    #[test]
+    fn test_reaggregation_keeps_history_ordered() {
+        let mut context = get_context(accounts(0));
+        context.block_timestamp(500);
+        let mut contract = setup_with_nodes(&mut context, 2, 1);
+        report_as(&mut context, &mut contract, 0, 29000);
+        report_as(&mut context, &mut contract, 1, 29000);
+
+        context.block_timestamp(1_000);
+        report_as(&mut context, &mut contract, 0, 30000);
+        context.block_timestamp(2_000);
+        report_as(&mut context, &mut contract, 1, 31000);
+        assert_eq!(
+            contract
+                .get_price_at("near".to_string(), 2_500)
+                .unwrap()
+                .multiplier,
+            30500
+        );
+
+        // Dropping node 1 re-aggregates node 0's older report as a correction
+        // of the price in force since t=2000
+        context.predecessor_account_id(operator(1));
+        testing_env!(context.build());
+        contract.retire_node(node(1));
+        let price = contract.get_price("near".to_string()).unwrap().price;
+        assert_eq!((price.timestamp, price.multiplier), (2_000, 30000));
+
+        let history: Vec<(u64, u128)> = contract
+            .get_price_history("near".to_string(), 0, 10)
+            .iter()
+            .map(|price| (price.timestamp, price.multiplier))
+            .collect();
+        assert_eq!(history, vec![(500, 29000), (2_000, 30000)]);
+        assert_eq!(
+            contract
+                .get_price_at("near".to_string(), 2_500)
+                .unwrap()
+                .multiplier,
+            30000
+        );
+        assert_eq!(
+            contract
+                .get_price_at("near".to_string(), 1_500)
+                .unwrap()
+                .multiplier,
+            29000
+        );
+
+        context.block_timestamp(3_000);
+        testing_env!(context.build());
+        let twap = contract.get_twap("near".to_string(), 1_000).unwrap();
+        assert_eq!(twap.multiplier, 30000);
+    }
+
+    #[test]
+    fn test_state_changes_emit_nep297_events() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        report_as(&mut context, &mut contract, 0, 35000);
+
+        let logs = get_logs();
+        assert_eq!(
+            logs[0],
+            r#"EVENT_JSON:{"standard":"tee-oracle","version":"1.0.0","event":"round_opened","data":{"asset_id":"near","round_id":1,"deadline":60000000000}}"#
+        );
+        assert_eq!(
+            logs[1],
+            r#"EVENT_JSON:{"standard":"tee-oracle","version":"1.0.0","event":"price_updated","data":{"asset_id":"near","round_id":1,"multiplier":"35000","decimals":4,"conf":"0","timestamp":0,"num_sources":1}}"#
+        );
+        assert_eq!(
+            logs[2],
+            r#"EVENT_JSON:{"standard":"tee-oracle","version":"1.0.0","event":"price_reported","data":{"asset_id":"near","node_account":"node0.near","multiplier":"35000","decimals":4}}"#
+        );
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.pause();
+        assert_eq!(
+            get_logs(),
+            vec![r#"EVENT_JSON:{"standard":"tee-oracle","version":"1.0.0","event":"paused"}"#]
+        );
+    }
+
+    #[test]
+    fn test_batch_price_reporting() {
+        let mut context = get_context(accounts(0));
+        context.block_timestamp(1_000);
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+
+        let mut btc = near_asset(1);
+        btc.id = "btc".to_string();
+        btc.symbol = "BTC".to_string();
+        btc.name = "Bitcoin".to_string();
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.add_asset(btc);
+
+        context.predecessor_account_id(node(0));
+        testing_env!(context.build());
+        let result = contract.report_prices(vec![
+            PriceInput {
+                asset_id: "near".to_string(),
+                multiplier: 35000,
+                decimals: 4,
+            },
+            PriceInput {
+                asset_id: "btc".to_string(),
+                multiplier: 65_000,
+                decimals: 0,
+            },
+            PriceInput {
+                asset_id: "near".to_string(),
+                multiplier: 36000,
+                decimals: 4,
+            },
+            PriceInput {
+                asset_id: "doge".to_string(),
+                multiplier: 1,
+                decimals: 4,
+            },
+        ]);
+
+        assert_eq!(result.accepted, vec!["near".to_string()]);
+        let reasons: Vec<&str> = result.rejected.iter().map(|r| r.reason.as_str()).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                "Decimals mismatch with asset definition",
+                "Duplicate asset in batch",
+                "Asset not found"
+            ]
+        );
+
+        assert_eq!(
+            contract
+                .get_price("near".to_string())
+                .unwrap()
+                .price
+                .multiplier,
+            35000
+        );
+        assert!(contract.get_price("btc".to_string()).is_none());
+        assert_eq!(
+            contract.get_node_details(node(0)).unwrap().last_report,
+            1_000
+        );
+    }
+
+    #[test]
+    fn test_rounds_close_on_full_participation_or_deadline() {
+        let mut context = get_context(accounts(0));
+        context.block_timestamp(1_000);
+        let mut contract = setup_with_nodes(&mut context, 3, 2);
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.update_config(None, None, None, None, Some(500));
+
+        // Round 1 closes as soon as all three nodes have reported
+        report_as(&mut context, &mut contract, 0, 35000);
+        report_as(&mut context, &mut contract, 1, 36000);
+        assert!(contract.get_price("near".to_string()).is_none());
+        assert_eq!(contract.get_open_round("near".to_string()).unwrap().id, 1);
+        report_as(&mut context, &mut contract, 2, 37000);
+        let price = contract.get_price("near".to_string()).unwrap();
+        assert_eq!(price.round_id, 1);
+        assert_eq!(price.price.multiplier, 36000);
+        assert!(contract.get_open_round("near".to_string()).is_none());
+
+        // Round 2 only gets two reports and is finalised after its deadline
+        context.block_timestamp(2_000);
+        report_as(&mut context, &mut contract, 0, 40000);
+        report_as(&mut context, &mut contract, 1, 41000);
+        assert_eq!(contract.get_price("near".to_string()).unwrap().round_id, 1);
+
+        context.block_timestamp(2_600);
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+        contract.finalize_round("near".to_string());
+        let price = contract.get_price("near".to_string()).unwrap();
+        assert_eq!(price.round_id, 2);
+        assert_eq!(price.price.multiplier, 40500);
+        assert!(contract
+            .get_price_reports("near".to_string())
+            .iter()
+            .all(|r| r.price.round_id == 2));
+
+        // Round 3 expires with a single report and round 4 opens
+        context.block_timestamp(3_000);
+        report_as(&mut context, &mut contract, 0, 50000);
+        context.block_timestamp(3_600);
+        report_as(&mut context, &mut contract, 1, 51000);
+        assert_eq!(contract.get_price("near".to_string()).unwrap().round_id, 2);
+        assert_eq!(contract.get_open_round("near".to_string()).unwrap().id, 4);
+    }
+
+    #[test]
+    fn test_rounds_close_at_quorum() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 3, 2);
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.update_asset(Asset {
+            max_deviation_bps: 500,
+            ..near_asset(2)
+        });
+
+        // An outlier doesn't count towards quorum, so the round stays open
+        report_as(&mut context, &mut contract, 0, 35000);
+        report_as(&mut context, &mut contract, 1, 70000);
+        assert!(contract.get_price("near".to_string()).is_none());
+
+        // The second report in band closes it without waiting for node 2
+        report_as(&mut context, &mut contract, 2, 35500);
+        assert!(contract.get_open_round("near".to_string()).is_none());
+        let price = contract.get_price("near".to_string()).unwrap();
+        assert_eq!(price.price.multiplier, 35250);
+        assert_eq!(price.num_sources, 2);
+    }
+
+    #[test]
+    fn test_migrate_from_v1_state() {
+        use migration::*;
+
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut old = OracleV1 {
+            owner: accounts(0),
+            paused: true,
+            assets: UnorderedMap::new(StorageKey::Assets),
+            asset_list: Vector::new(StorageKey::AssetList),
+            price_reports: LookupMap::new(StorageKey::PriceReports),
+            aggregated_prices: LookupMap::new(StorageKey::AggregatedPrices),
+            authorized_nodes: UnorderedSet::new(StorageKey::AuthorizedNodes),
+            node_details: LookupMap::new(StorageKey::NodeDetails),
+            whitelisted_operators: UnorderedSet::new(StorageKey::WhitelistedOperators),
+            approved_code_hashes: UnorderedSet::new(StorageKey::ApprovedCodeHashes),
+            approved_enclaves: LookupMap::new(StorageKey::ApprovedEnclaves),
+            operator_to_node: LookupMap::new(StorageKey::OperatorNodes),
+            node_to_operator: LookupMap::new(StorageKey::NodeToOperator),
+            recency_threshold: 300_000_000_000,
+            min_report_count: 2,
+            attestation_max_age: 1_000,
+            admin_proposers: UnorderedSet::new(StorageKey::AdminProposers),
+            admin_voters: UnorderedSet::new(StorageKey::AdminVoters),
+            admin_timelock_delay: 0,
+            admin_quorum_bps: 5000,
+            proposals: UnorderedMap::new(StorageKey::AdminProposals),
+            proposal_counter: 1,
+        };
+        old.assets.insert(
+            &"near".to_string(),
+            &AssetV1 {
+                id: "near".to_string(),
+                symbol: "NEAR".to_string(),
+                name: "NEAR Protocol".to_string(),
+                decimals: 4,
+                active: true,
+                min_sources: 2,
+            },
+        );
+        old.asset_list.push(&"near".to_string());
+        old.aggregated_prices.insert(
+            &"near".to_string(),
+            &PriceV1 {
+                multiplier: 35000,
+                decimals: 4,
+                timestamp: 0,
+            },
+        );
+        old.authorized_nodes.insert(&node(0));
+        old.whitelisted_operators.insert(&operator(0));
+        old.operator_to_node.insert(&operator(0), &node(0));
+        old.approved_code_hashes.insert(&"hash1".to_string());
+        old.approved_enclaves
+            .insert(&"hash1".to_string(), &"AB12".to_string());
+        old.node_details.insert(
+            &node(0),
+            &OracleNodeV1 {
+                account_id: node(0),
+                operator_id: operator(0),
+                registered_at: 0,
+                code_hash: "hash1".to_string(),
+                last_report: 0,
+                active: true,
+            },
+        );
+        old.proposals.insert(
+            &1,
+            &AdminProposalV1 {
+                id: 1,
+                proposer: accounts(1),
+                action: AdminActionV1::UpdateConfig {
+                    recency_threshold: Some(1),
+                    min_report_count: None,
+                },
+                scheduled_for: 0,
+                approvals: vec![accounts(1)],
+                executed: false,
+            },
+        );
+        env::state_write(&old);
+
+        let contract = Oracle::migrate();
+        assert_eq!(contract.get_state_version(), STATE_VERSION);
+        assert!(contract.paused);
+        assert_eq!(contract.attestation_max_age, 1_000);
+        assert_eq!(contract.get_assets()[0].min_sources, 2);
+        let price = contract.get_price("near".to_string()).unwrap();
+        assert_eq!(price.price.multiplier, 35000);
+        assert_eq!(price.price.conf, 0);
+        assert_eq!(contract.get_node_details(node(0)).unwrap().weight, 1);
+        assert_eq!(contract.get_operator_nodes(operator(0)), vec![node(0)]);
+        assert_eq!(
+            contract
+                .get_enclave_policy("hash1".to_string())
+                .unwrap()
+                .measurements,
+            vec!["ab12".to_string()]
+        );
+        assert!(matches!(
+            contract.list_proposals()[0].action,
+            AdminAction::UpdateConfig {
+                recency_threshold: Some(1),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_migrate_from_v2_state() {
+        use migration::*;
+
+        let mut context = get_context(accounts(0));
+        context.block_timestamp(5_000);
+        testing_env!(context.build());
+
+        let mut old = OracleV2 {
+            owner: accounts(0),
+            paused: false,
+            assets: UnorderedMap::new(StorageKey::Assets),
+            asset_list: Vector::new(StorageKey::AssetList),
+            price_reports: LookupMap::new(StorageKey::PriceReports),
+            aggregated_prices: LookupMap::new(StorageKey::AggregatedPrices),
+            authorized_nodes: UnorderedSet::new(StorageKey::AuthorizedNodes),
+            node_details: LookupMap::new(StorageKey::NodeDetails),
+            whitelisted_operators: UnorderedSet::new(StorageKey::WhitelistedOperators),
+            approved_code_hashes: UnorderedSet::new(StorageKey::ApprovedCodeHashes),
+            approved_enclaves: LookupMap::new(StorageKey::ApprovedEnclaves),
+            operator_to_node: LookupMap::new(StorageKey::OperatorNodes),
+            node_to_operator: LookupMap::new(StorageKey::NodeToOperator),
+            recency_threshold: 300_000_000_000,
+            min_report_count: 1,
+            attestation_max_age: 1_000,
+            admin_proposers: UnorderedSet::new(StorageKey::AdminProposers),
+            admin_voters: UnorderedSet::new(StorageKey::AdminVoters),
+            admin_timelock_delay: 0,
+            admin_quorum_bps: 5000,
+            proposals: UnorderedMap::new(StorageKey::AdminProposals),
+            proposal_counter: 1,
+            twap_samples: LookupMap::new(StorageKey::TwapSamples),
+            price_history: LookupMap::new(StorageKey::PriceHistory),
+            history_cursors: LookupMap::new(StorageKey::HistoryCursors),
+            history_depth: 50,
+            price_accumulators: LookupMap::new(StorageKey::PriceAccumulators),
+            ema_period: 1_000,
+            open_rounds: LookupMap::new(StorageKey::OpenRounds),
+            round_counters: LookupMap::new(StorageKey::RoundCounters),
+            round_duration: 2_000,
+        };
+        old.assets.insert(
+            &"near".to_string(),
+            &AssetV2 {
+                id: "near".to_string(),
+                symbol: "NEAR".to_string(),
+                name: "NEAR Protocol".to_string(),
+                decimals: 4,
+                active: false,
+                min_sources: 1,
+                max_deviation_bps: 500,
+                aggregation: AggregationMethodV2::Twap { window: 1_000 },
+            },
+        );
+        old.asset_list.push(&"near".to_string());
+        old.aggregated_prices.insert(
+            &"near".to_string(),
+            &Price {
+                multiplier: 35000,
+                decimals: 4,
+                timestamp: 0,
+                conf: 10,
+                round_id: 7,
+            },
+        );
+        old.round_counters.insert(&"near".to_string(), &7);
+        old.authorized_nodes.insert(&node(0));
+        old.whitelisted_operators.insert(&operator(0));
+        old.operator_to_node.insert(&operator(0), &node(0));
+        old.node_to_operator.insert(&node(0), &operator(0));
+        old.approved_code_hashes.insert(&"hash1".to_string());
+        old.approved_enclaves
+            .insert(&"hash1".to_string(), &"AB12".to_string());
+        old.node_details.insert(
+            &node(0),
+            &OracleNodeV2 {
+                account_id: node(0),
+                operator_id: operator(0),
+                registered_at: 0,
+                code_hash: "hash1".to_string(),
+                last_report: 0,
+                active: true,
+                outlier_reports: 3,
+                weight: 4,
+            },
+        );
+        old.proposals.insert(
+            &1,
+            &AdminProposalV2 {
+                id: 1,
+                proposer: accounts(1),
+                action: AdminActionV2::SetNodeWeight {
+                    account_id: node(0),
+                    weight: 2,
+                },
+                scheduled_for: 0,
+                approvals: vec![accounts(1)],
+                executed: false,
+            },
+        );
+        old.proposals.insert(
+            &2,
+            &AdminProposalV2 {
+                id: 2,
+                proposer: accounts(1),
+                action: AdminActionV2::SetAggregationMethod {
+                    asset_id: "near".to_string(),
+                    method: AggregationMethodV2::Twap { window: 2_000 },
+                },
+                scheduled_for: 0,
+                approvals: vec![accounts(1)],
+                executed: false,
+            },
+        );
+        old.proposal_counter = 2;
+        env::state_write(&old);
+        write_state_version(2);
+
+        let contract = Oracle::migrate();
+        assert_eq!(contract.get_state_version(), STATE_VERSION);
+        let asset = &contract.get_assets()[0];
+        assert!(asset.status == AssetStatus::QueryFrozen);
+        assert_eq!(asset.max_deviation_bps, 500);
+        assert!(matches!(
+            asset.aggregation,
+            AggregationMethod::Twap { window: 1_000 }
+        ));
+        assert_eq!(contract.round_duration, 2_000);
+        assert_eq!(contract.round_counters.get(&"near".to_string()), Some(7));
+        assert_eq!(
+            contract
+                .aggregated_prices
+                .get(&"near".to_string())
+                .unwrap()
+                .conf,
+            10
+        );
+
+        // The node keeps its stats, is on record with its code hash's
+        // measurement and has a full window to re-attest
+        let details = contract.get_node_details(node(0)).unwrap();
+        assert_eq!(details.weight, 4);
+        assert_eq!(details.measurement, "ab12");
+        assert_eq!(details.attested_at, 5_000);
+        assert_eq!(contract.get_operator_nodes(operator(0)), vec![node(0)]);
+        let proposals = contract.list_proposals();
+        assert!(matches!(
+            proposals[0].action,
+            AdminAction::SetNodeWeight { weight: 2, .. }
+        ));
+        assert!(matches!(
+            proposals[1].action,
+            AdminAction::SetAggregationMethod {
+                method: AggregationMethod::Twap { window: 2_000 },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only owner or the contract itself can migrate")]
+    fn test_migrate_rejects_other_callers() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let contract = Oracle::new(accounts(0), 300_000_000_000, 1);
+        env::state_write(&contract);
+
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        Oracle::migrate();
+    }
+
+    #[test]
+    fn test_asset_management_via_governance() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        report_as(&mut context, &mut contract, 0, 35000);
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.configure_admin_role(vec![accounts(1)], vec![accounts(1)], 0, 5000);
+
+        let mut btc = near_asset(1);
+        btc.id = "btc".to_string();
+        btc.symbol = "BTC".to_string();
+        btc.name = "Bitcoin".to_string();
+
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        for action in [
+            AdminAction::AddAsset { asset: btc.clone() },
+            AdminAction::UpdateAsset {
+                asset: Asset {
+                    min_sources: 3,
+                    ..btc
+                },
+            },
+            AdminAction::DeactivateAsset {
+                asset_id: "btc".to_string(),
+            },
+            AdminAction::RemoveAsset {
+                asset_id: "near".to_string(),
+            },
+        ] {
+            contract.propose_action(action);
+            contract.execute_proposal(contract.proposal_counter);
+        }
+
+        let assets = contract.get_assets();
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].id, "btc");
+        assert_eq!(assets[0].min_sources, 3);
+        assert_eq!(assets[0].status, AssetStatus::QueryFrozen);
+
+        assert!(contract.get_price("near".to_string()).is_none());
+        assert!(contract
+            .get_price_history("near".to_string(), 0, 10)
+            .is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Asset decimals cannot be changed")]
+    fn test_update_asset_rejects_decimals_change() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = Oracle::new(accounts(0), 300_000_000_000, 1);
+        contract.add_asset(near_asset(1));
+        contract.update_asset(Asset {
+            decimals: 8,
+            ..near_asset(1)
+        });
+    }
+
+    #[test]
+    fn test_asset_lifecycle() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        report_as(&mut context, &mut contract, 0, 35000);
+        assert!(contract.get_price("near".to_string()).is_some());
+
+        // Reporting-only keeps aggregating but hides prices from consumers
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_asset_status("near".to_string(), AssetStatus::ReportingOnly);
+        context.block_timestamp(1_000);
+        report_as(&mut context, &mut contract, 0, 36000);
+        assert!(contract.get_price("near".to_string()).is_none());
+        assert!(contract.get_price_unsafe("near".to_string()).is_none());
+        assert!(contract.get_price_data().is_empty());
+
+        // Frozen rejects reports and keeps the last aggregate for later
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_asset_status("near".to_string(), AssetStatus::QueryFrozen);
+        context.predecessor_account_id(node(0));
+        testing_env!(context.build());
+        let result = contract.report_prices(vec![PriceInput {
+            asset_id: "near".to_string(),
+            multiplier: 99000,
+            decimals: 4,
+        }]);
+        assert_eq!(result.rejected[0].reason, "Asset is not accepting reports");
+        assert!(contract.get_ema_price_unsafe("near".to_string()).is_none());
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_asset_status("near".to_string(), AssetStatus::Active);
+        let price = contract.get_price("near".to_string()).unwrap();
+        assert_eq!(price.price.multiplier, 36000);
+
+        // Deactivating halts the feed until it is resumed
+        contract.deactivate_asset("near".to_string());
+        assert!(contract.get_price("near".to_string()).is_none());
+        contract.update_asset(near_asset(1));
+        assert_eq!(
+            contract
+                .get_price("near".to_string())
+                .unwrap()
+                .price
+                .multiplier,
+            36000
+        );
+
+        // Delisting halts the feed for good without deleting its history
+        contract.set_asset_status("near".to_string(), AssetStatus::Delisted);
+        assert!(contract.get_price("near".to_string()).is_none());
+        assert_eq!(
+            contract.get_price_history("near".to_string(), 0, 10).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_suspended_node_excluded_and_reinstated() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 3, 2);
+        report_as(&mut context, &mut contract, 0, 35000);
+        report_as(&mut context, &mut contract, 1, 35100);
+        report_as(&mut context, &mut contract, 2, 90000);
+        assert_eq!(
+            contract.get_price("near".to_string()).unwrap().num_sources,
+            3
+        );
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.suspend_node(node(2), Some(1_000));
+        let node_details = contract.get_node_details(node(2)).unwrap();
+        assert!(!node_details.active);
+        assert_eq!(node_details.suspended_until, 1_000);
+
+        // Its report is dropped from the current price
+        let price = contract.get_price("near".to_string()).unwrap();
+        assert_eq!(price.num_sources, 2);
+        assert_eq!(price.price.multiplier, 35050);
+
+        // Rounds close once every active node has reported
+        context.block_timestamp(500);
+        report_as(&mut context, &mut contract, 0, 36000);
+        report_as(&mut context, &mut contract, 1, 36000);
+        assert!(contract.get_open_round("near".to_string()).is_none());
+
+        // Once the suspension runs out the next report reinstates the node
+        context.block_timestamp(1_000);
+        report_as(&mut context, &mut contract, 2, 36000);
+        assert!(contract.get_node_details(node(2)).unwrap().active);
+    }
+
+    #[test]
+    fn test_failed_round_keeps_last_aggregate() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 3, 2);
+
+        let mut asset = near_asset(2);
+        asset.max_deviation_bps = 500;
+        asset.round_quorum = 3;
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.update_asset(asset);
+
+        report_as(&mut context, &mut contract, 0, 35000);
+        report_as(&mut context, &mut contract, 1, 35100);
+        report_as(&mut context, &mut contract, 2, 35200);
+        assert_eq!(contract.get_price("near".to_string()).unwrap().round_id, 1);
+
+        // Only one report of the next round is within band of the median
+        context.block_timestamp(1_000);
+        report_as(&mut context, &mut contract, 0, 35000);
+        report_as(&mut context, &mut contract, 1, 50000);
+        report_as(&mut context, &mut contract, 2, 70000);
+        assert!(contract.get_open_round("near".to_string()).is_none());
+        let price = contract.get_price("near".to_string()).unwrap();
+        assert_eq!(price.round_id, 1);
+        assert_eq!(price.price.multiplier, 35100);
+        assert_eq!(price.num_sources, 3);
+    }
+
+    #[test]
+    fn test_outlier_streak_jails_node() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 3, 1);
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_jail_policy(JailPolicy {
+            jail_duration: 10_000,
+            max_consecutive_outliers: 2,
+            heartbeat_timeout: 0,
+        });
+        contract.update_asset(Asset {
+            max_deviation_bps: 100,
+            round_quorum: 3,
+            ..near_asset(1)
+        });
+
+        for round in 0..2 {
+            context.block_timestamp(round * 100);
+            report_as(&mut context, &mut contract, 0, 35000);
+            report_as(&mut context, &mut contract, 1, 35000);
+            report_as(&mut context, &mut contract, 2, 50000);
+        }
+
+        let node_details = contract.get_node_details(node(2)).unwrap();
+        assert!(!node_details.active);
+        assert_eq!(node_details.outlier_reports, 2);
+        assert_eq!(node_details.suspended_until, 10_100);
+    }
+
+    #[test]
+    fn test_idle_node_jailed_by_anyone() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 2, 1);
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_jail_policy(JailPolicy {
+            heartbeat_timeout: 100,
+            ..JailPolicy::default()
+        });
+
+        context.predecessor_account_id(accounts(3));
+        context.block_timestamp(200);
+        testing_env!(context.build());
+        contract.jail_idle_node(node(0));
+
+        let node_details = contract.get_node_details(node(0)).unwrap();
+        assert!(!node_details.active);
+        assert_eq!(
+            node_details.suspended_until,
+            200 + JailPolicy::default().jail_duration
+        );
+        assert!(contract.get_node_details(node(1)).unwrap().active);
+    }
+
+    #[test]
+    #[should_panic(expected = "Node is suspended")]
+    fn test_jailed_node_cannot_report() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 2, 1);
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_jail_policy(JailPolicy {
+            heartbeat_timeout: 100,
+            ..JailPolicy::default()
+        });
+
+        context.predecessor_account_id(accounts(3));
+        context.block_timestamp(200);
+        testing_env!(context.build());
+        contract.jail_idle_node(node(0));
+
+        report_as(&mut context, &mut contract, 0, 35000);
+    }
+
+    #[test]
+    fn test_lapsed_attestation_requires_reattestation() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 2, 1);
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_reattestation_window(1_000);
+        assert_eq!(
+            contract.get_node_attestation(node(0)).unwrap().expires_at,
+            1_000
+        );
+
+        // Node 0 re-attests, node 1 lets its attestation lapse
+        context.predecessor_account_id(node(0));
+        context.block_timestamp(1_500);
+        testing_env!(context.build());
+        contract.register_node(
+            "hash1".to_string(),
+            attestation_for(MR_ENCLAVE, 1_500),
+            None,
+        );
+        assert_eq!(contract.get_lapsed_nodes(), vec![node(1)]);
+        assert!(contract.get_node_attestation(node(1)).unwrap().lapsed);
+
+        // The lapsed node no longer holds rounds open
+        report_as(&mut context, &mut contract, 0, 35000);
+        assert!(contract.get_open_round("near".to_string()).is_none());
+        assert!(contract.get_price("near".to_string()).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "Node attestation has lapsed")]
+    fn test_lapsed_node_cannot_report() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_reattestation_window(1_000);
+
+        context.block_timestamp(1_500);
+        report_as(&mut context, &mut contract, 0, 35000);
+    }
+
+    #[test]
+    fn test_enclave_rollout_across_measurements() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        let new_build = [0x22; 32];
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.approve_attestation(
+            "hash1".to_string(),
+            attestation::to_hex(&new_build).to_uppercase(),
+        );
+        let policy = contract.get_enclave_policy("hash1".to_string()).unwrap();
+        assert_eq!(
+            policy.measurements,
+            vec![
+                attestation::to_hex(&MR_ENCLAVE),
+                attestation::to_hex(&new_build)
+            ]
+        );
+
+        // The node upgrades while the old build is still approved
+        context.predecessor_account_id(node(0));
+        testing_env!(context.build());
+        contract.register_node(
+            "hash1".to_string(),
+            attestation_for(new_build, env::block_timestamp()),
+            None,
+        );
+
+        // Retiring the old build leaves the upgraded node authorized
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.remove_enclave_measurement("hash1".to_string(), attestation::to_hex(&MR_ENCLAVE));
+        assert!(contract.is_authorized(node(0)));
+        assert_eq!(
+            contract.get_node_details(node(0)).unwrap().measurement,
+            attestation::to_hex(&new_build)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Attestation measurement mismatch")]
+    fn test_retired_measurement_cannot_register() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.approve_attestation("hash1".to_string(), attestation::to_hex(&[0x22; 32]));
+        contract.remove_enclave_measurement("hash1".to_string(), attestation::to_hex(&MR_ENCLAVE));
+
+        context.predecessor_account_id(node(0));
+        testing_env!(context.build());
+        contract.register_node(
+            "hash1".to_string(),
+            attestation_for(MR_ENCLAVE, env::block_timestamp()),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_signer_policy_accepts_signed_builds() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+
+        // Trust any build signed by MRSIGNER 0x33.. with product ID 1 and SVN 2+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_signer_policy(
+            "hash1".to_string(),
+            SignerPolicy {
+                mr_signer: Some(attestation::to_hex(&[0x33; 32])),
+                isv_prod_id: Some(1),
+                min_isv_svn: 2,
+            },
+        );
+
+        // An unlisted measurement from that signer registers
+        context.predecessor_account_id(node(0));
+        testing_env!(context.build());
+        let quote = attestation::test_utils::sgx_quote_signed_by(
+            [0x44; 32],
+            [0x33; 32],
+            1,
+            2,
+            report_data_for(&env::signer_account_pk(), env::block_timestamp()),
+        );
+        contract.register_node(
+            "hash1".to_string(),
+            AttestationData {
+                quote: quote.into(),
+            },
+            None,
+        );
+        assert!(contract.is_authorized(node(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Enclave SVN below minimum")]
+    fn test_signer_policy_rejects_old_svn() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+
+        // Trust any build signed by MRSIGNER 0x33.. with product ID 1 and SVN 2+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_signer_policy(
+            "hash1".to_string(),
+            SignerPolicy {
+                mr_signer: Some(attestation::to_hex(&[0x33; 32])),
+                isv_prod_id: Some(1),
+                min_isv_svn: 2,
+            },
+        );
+
+        // The same signer's build below the minimum SVN
+        context.predecessor_account_id(node(0));
+        testing_env!(context.build());
+        let quote = attestation::test_utils::sgx_quote_signed_by(
+            [0x44; 32],
+            [0x33; 32],
+            1,
+            1,
+            report_data_for(&env::signer_account_pk(), env::block_timestamp()),
+        );
+        contract.register_node(
+            "hash1".to_string(),
+            AttestationData {
+                quote: quote.into(),
+            },
+            None,
+        );
+    }
+
+    #[test]
+    fn test_removing_code_hash_deauthorizes_its_nodes() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 3, 1);
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.approve_code_hash("hash2".to_string());
+        approve_test_enclave(&mut contract, "hash2");
+
+        context.predecessor_account_id(node(2));
+        testing_env!(context.build());
+        contract.register_node(
+            "hash2".to_string(),
+            attestation_for(MR_ENCLAVE, env::block_timestamp()),
+            None,
+        );
+
+        report_as(&mut context, &mut contract, 0, 35000);
+        report_as(&mut context, &mut contract, 1, 35100);
+        report_as(&mut context, &mut contract, 2, 90000);
+        assert_eq!(
+            contract.get_price("near".to_string()).unwrap().num_sources,
+            3
+        );
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.remove_code_hash("hash2".to_string());
+        assert_eq!(
+            get_logs()[1],
+            r#"EVENT_JSON:{"standard":"tee-oracle","version":"1.0.0","event":"nodes_deauthorized","data":{"code_hash":"hash2","mr_enclave":null,"node_accounts":["node2.near"]}}"#
+        );
+        assert!(!contract.is_authorized(node(2)));
+        assert!(contract.get_node_details(node(2)).is_some());
+
+        let price = contract.get_price("near".to_string()).unwrap();
+        assert_eq!(price.num_sources, 2);
+        assert_eq!(price.price.multiplier, 35050);
+    }
+
+    #[test]
+    fn test_reputation_weighted_median_favours_track_record() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 4, 1);
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_aggregation_method(
+            "near".to_string(),
+            AggregationMethod::ReputationWeightedMedian,
+        );
+
+        // Node 3 keeps reporting away from the aggregate
+        for round in 0..3 {
+            context.block_timestamp(round * 100);
+            for i in 0..3 {
+                report_as(&mut context, &mut contract, i, 35000);
+            }
+            report_as(&mut context, &mut contract, 3, 40000);
+        }
+        let reputation = contract.get_node_details(node(0)).unwrap().reputation;
+        assert_eq!(reputation.rounds_reported, 3);
+        assert_eq!(reputation.score, 300);
+        let outlier = contract.get_node_details(node(3)).unwrap().reputation;
+        assert_eq!(outlier.avg_deviation_bps, 1428);
+        assert!(outlier.score < reputation.score);
+
+        // A plain median would land between the two camps
+        context.block_timestamp(300);
+        report_as(&mut context, &mut contract, 0, 35000);
+        report_as(&mut context, &mut contract, 1, 35000);
+        report_as(&mut context, &mut contract, 2, 40000);
+        report_as(&mut context, &mut contract, 3, 40000);
+        assert_eq!(
+            contract
+                .get_price("near".to_string())
+                .unwrap()
+                .price
+                .multiplier,
+            35000
+        );
+    }
+
+    fn bond_as(context: &mut VMContextBuilder, contract: &mut Oracle, index: usize, near: u128) {
+        context
+            .predecessor_account_id(operator(index))
+            .attached_deposit(NearToken::from_near(near));
+        testing_env!(context.build());
+        contract.deposit_bond();
+        context.attached_deposit(NearToken::from_yoctonear(0));
+    }
+
+    // Three bonded operators; node 2 reports 1428 bps away from the aggregate
+    fn setup_deviating_node(context: &mut VMContextBuilder) -> Oracle {
+        let mut contract = setup_with_nodes(context, 3, 1);
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_treasury(accounts(5));
+        contract.set_staking_policy(StakingPolicy {
+            min_bond: NearToken::from_near(10),
+            unbonding_period: 1_000,
+            slash_deviation_bps: 500,
+            slash_bps: 1_000,
+        });
+        for i in 0..3 {
+            bond_as(context, &mut contract, i, 10);
+        }
+
+        report_as(context, &mut contract, 0, 35000);
+        report_as(context, &mut contract, 1, 35000);
+        report_as(context, &mut contract, 2, 40000);
+        contract
+    }
+
+    #[test]
+    fn test_deviation_slashing_goes_to_treasury() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_deviating_node(&mut context);
+
+        // Anyone can prove the deviation from the published aggregate
+        context.predecessor_account_id(accounts(4));
+        testing_env!(context.build());
+        contract.slash_deviation("near".to_string(), node(2));
+        assert_eq!(
+            contract.get_bond(operator(2)).unwrap().bonded,
+            NearToken::from_near(9)
+        );
+        assert_eq!(
+            get_logs()[0],
+            r#"EVENT_JSON:{"standard":"tee-oracle","version":"1.0.0","event":"operator_slashed","data":{"operator_id":"operator2.near","amount":"1000000000000000000000000","treasury":"fargo","reason":"Deviation of 1428 bps on near"}}"#
+        );
+
+        // Below the minimum bond the operator's nodes stop counting
+        assert_eq!(
+            contract.get_price("near".to_string()).unwrap().num_sources,
+            3
+        );
+        context.block_timestamp(100);
+        report_as(&mut context, &mut contract, 0, 35000);
+        report_as(&mut context, &mut contract, 1, 35000);
+        assert!(contract.get_open_round("near".to_string()).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Report already slashed")]
+    fn test_report_slashed_only_once() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_deviating_node(&mut context);
+
+        context.predecessor_account_id(accounts(4));
+        testing_env!(context.build());
+        contract.slash_deviation("near".to_string(), node(2));
+        contract.slash_deviation("near".to_string(), node(2));
+    }
+
+    #[test]
+    fn test_unbonding_funds_stay_slashable() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_deviating_node(&mut context);
+
+        // Unbonding everything right after a bad report doesn't escape the slash
+        context.predecessor_account_id(operator(2));
+        testing_env!(context.build());
+        contract.unbond(NearToken::from_near(10));
+        let bond = contract.get_bond(operator(2)).unwrap();
+        assert_eq!(bond.unbonding, NearToken::from_near(10));
+        assert_eq!(bond.withdrawable_at, 1_000);
+
+        context.predecessor_account_id(accounts(4));
+        testing_env!(context.build());
+        contract.slash_deviation("near".to_string(), node(2));
+        assert_eq!(
+            contract.get_bond(operator(2)).unwrap().unbonding,
+            NearToken::from_near(9)
+        );
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.slash_operator(operator(2), NearToken::from_near(3), "Downtime".to_string());
+        assert_eq!(
+            contract.get_bond(operator(2)).unwrap().unbonding,
+            NearToken::from_near(6)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Bond is still unbonding")]
+    fn test_withdraw_before_unbonding_period() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        bond_as(&mut context, &mut contract, 0, 10);
+
+        context.predecessor_account_id(operator(0));
+        testing_env!(context.build());
+        contract.unbond(NearToken::from_near(10));
+
+        context.block_timestamp(604_799_999_999_999);
+        testing_env!(context.build());
+        contract.withdraw_bond();
+    }
+
+    #[test]
+    fn test_fees_are_distributed_by_accepted_reports() {
+        let mut context = get_context(accounts(0));
+        context.block_timestamp(1_000);
+        let mut contract = setup_with_nodes(&mut context, 3, 2);
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.update_config(None, None, None, None, Some(500));
+        contract.set_fee_policy(FeePolicy {
+            price_fee: NearToken::from_near(1),
+            subscription_fee: NearToken::from_near(3),
+            subscription_period: 1_000,
+            ..FeePolicy::default()
+        });
+
+        // Operators 0 and 1 are in both aggregates, operator 2 only in the first
+        report_as(&mut context, &mut contract, 0, 35000);
+        report_as(&mut context, &mut contract, 1, 36000);
+        report_as(&mut context, &mut contract, 2, 37000);
+        context.block_timestamp(2_000);
+        report_as(&mut context, &mut contract, 0, 40000);
+        report_as(&mut context, &mut contract, 1, 41000);
+        context.block_timestamp(2_600);
+        testing_env!(context.build());
+        contract.finalize_round("near".to_string());
+        assert_eq!(contract.get_reward_points(operator(0)), 2);
+        assert_eq!(contract.get_reward_points(operator(2)), 1);
+
+        // Two whole periods are bought and the remaining 1 NEAR refunded
+        context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(NearToken::from_near(7));
+        testing_env!(context.build());
+        assert_eq!(contract.subscribe(None), 4_600);
+        context.attached_deposit(NearToken::from_yoctonear(0));
+        testing_env!(context.build());
+        assert_eq!(contract.pull_price("near".to_string()).round_id, 2);
+
+        context
+            .predecessor_account_id(accounts(5))
+            .attached_deposit(NearToken::from_near(1));
+        testing_env!(context.build());
+        contract.pull_price("near".to_string());
+        assert_eq!(contract.get_reward_pool(), NearToken::from_near(7));
+
+        context.attached_deposit(NearToken::from_yoctonear(0));
+        testing_env!(context.build());
+        contract.distribute_rewards(None);
+        assert_eq!(contract.get_reward_pool(), NearToken::from_near(0));
+        assert_eq!(
+            contract.get_rewards(operator(0)),
+            NearToken::from_millinear(2_800)
+        );
+        assert_eq!(
+            contract.get_rewards(operator(2)),
+            NearToken::from_millinear(1_400)
+        );
+        assert_eq!(contract.get_reward_points(operator(0)), 0);
+
+        context.predecessor_account_id(operator(2));
+        testing_env!(context.build());
+        contract.claim_rewards();
+        assert_eq!(contract.get_rewards(operator(2)), NearToken::from_near(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Attached deposit below the price fee")]
+    fn test_expired_subscription_pulls_are_charged() {
+        let mut context = get_context(accounts(0));
+        context.block_timestamp(1_000);
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_fee_policy(FeePolicy {
+            price_fee: NearToken::from_near(1),
+            subscription_fee: NearToken::from_near(3),
+            subscription_period: 1_000,
+            ..FeePolicy::default()
+        });
+        report_as(&mut context, &mut contract, 0, 35000);
+
+        context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(NearToken::from_near(3));
+        testing_env!(context.build());
+        assert_eq!(contract.subscribe(None), 2_000);
+
+        context
+            .attached_deposit(NearToken::from_yoctonear(0))
+            .block_timestamp(2_000);
+        testing_env!(context.build());
+        contract.pull_price("near".to_string());
+    }
+
+    #[test]
+    fn test_rewards_distributed_in_pages() {
+        let mut context = get_context(accounts(0));
+        context.block_timestamp(1_000);
+        let mut contract = setup_with_nodes(&mut context, 3, 2);
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_fee_policy(FeePolicy {
+            price_fee: NearToken::from_near(3),
+            ..FeePolicy::default()
+        });
+        for i in 0..3 {
+            report_as(&mut context, &mut contract, i, 35000);
+        }
+        context
+            .predecessor_account_id(accounts(5))
+            .attached_deposit(NearToken::from_near(3));
+        testing_env!(context.build());
+        contract.pull_price("near".to_string());
+
+        context.attached_deposit(NearToken::from_yoctonear(0));
+        testing_env!(context.build());
+        assert!(!contract.distribute_rewards(Some(2)));
+        let distribution = contract.get_reward_distribution().unwrap();
+        assert_eq!(distribution.next_index, 2);
+        assert_eq!(distribution.distributed, NearToken::from_near(2));
+        assert_eq!(contract.get_reward_pool(), NearToken::from_near(0));
+
+        // Reports accepted meanwhile count towards the next distribution
+        context.block_timestamp(2_000);
+        for i in 0..3 {
+            report_as(&mut context, &mut contract, i, 35000);
+        }
+        assert_eq!(contract.get_reward_points(operator(0)), 1);
+        assert_eq!(contract.get_reward_points(operator(2)), 2);
+
+        context.predecessor_account_id(accounts(5));
+        testing_env!(context.build());
+        assert!(contract.distribute_rewards(Some(2)));
+        assert!(contract.get_reward_distribution().is_none());
+        for i in 0..3 {
+            assert_eq!(contract.get_rewards(operator(i)), NearToken::from_near(1));
+            assert_eq!(contract.get_reward_points(operator(i)), 1);
+        }
+    }
+
+    #[test]
+    fn test_oracle_call_delivers_prices_to_receiver() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_fee_policy(FeePolicy {
+            price_fee: NearToken::from_near(1),
+            ..FeePolicy::default()
+        });
+        report_as(&mut context, &mut contract, 0, 35000);
+
+        context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(NearToken::from_near(3));
+        testing_env!(context.build());
+        contract.oracle_call(
+            accounts(5),
+            vec!["near".to_string(), "btc".to_string()],
+            "borrow".to_string(),
+        );
+        // Only the asset with a price is charged
+        assert_eq!(contract.get_reward_pool(), NearToken::from_near(1));
+
+        let receipt = near_sdk::test_utils::get_created_receipts()
+            .into_iter()
+            .find(|receipt| receipt.receiver_id == accounts(5))
+            .unwrap();
+        let near_sdk::mock::MockAction::FunctionCallWeight {
+            method_name,
+            args,
+            attached_deposit,
+            ..
+        } = &receipt.actions[0]
+        else {
+            panic!("Expected a function call");
+        };
+        assert_eq!(method_name, b"oracle_on_call");
+        assert_eq!(*attached_deposit, NearToken::from_near(2));
+
+        let args: near_sdk::serde_json::Value = near_sdk::serde_json::from_slice(args).unwrap();
+        assert_eq!(args["sender_id"], accounts(4).to_string());
+        assert_eq!(args["msg"], "borrow");
+        let prices = &args["data"]["prices"];
+        assert_eq!(prices[0]["asset_id"], "near");
+        assert_eq!(prices[0]["data"]["price"]["multiplier"], 35000);
+        assert_eq!(prices[1]["asset_id"], "btc");
+        assert!(prices[1]["data"].is_null());
+    }
+
+    #[test]
+    fn test_oracle_call_refunds_deposit_when_receiver_fails() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        context
+            .predecessor_account_id(env::current_account_id())
+            .attached_deposit(NearToken::from_yoctonear(0));
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![near_sdk::PromiseResult::Failed],
+        );
+        contract.on_oracle_call(accounts(4), NearToken::from_near(2));
+
+        let receipt = near_sdk::test_utils::get_created_receipts()
+            .into_iter()
+            .find(|receipt| receipt.receiver_id == accounts(4))
+            .unwrap();
+        let near_sdk::mock::MockAction::Transfer { deposit, .. } = &receipt.actions[0] else {
+            panic!("Expected a transfer");
+        };
+        assert_eq!(*deposit, NearToken::from_near(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many assets requested")]
+    fn test_oracle_call_rejects_too_many_assets() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        context.predecessor_account_id(accounts(4));
+        testing_env!(context.build());
+        contract.oracle_call(
+            accounts(5),
+            vec!["near".to_string(); delivery::MAX_ORACLE_CALL_ASSETS + 1],
+            String::new(),
+        );
+    }
+
+    #[test]
+    fn test_price_updates_fire_on_deviation_and_heartbeat() {
+        let mut context = get_context(accounts(0));
+        context.block_timestamp(1_000);
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_fee_policy(FeePolicy {
+            notification_fee: NearToken::from_millinear(10),
+            ..FeePolicy::default()
+        });
+
+        context
+            .predecessor_account_id(accounts(5))
+            .attached_deposit(NearToken::from_near(1));
+        testing_env!(context.build());
+        contract.fund_price_updates(None);
+        context.attached_deposit(NearToken::from_yoctonear(0));
+        testing_env!(context.build());
+        contract.subscribe_price_updates("near".to_string(), 200, 1_000_000, None);
+
+        let delivered = || {
+            near_sdk::test_utils::get_created_receipts()
+                .iter()
+                .any(|receipt| receipt.receiver_id == accounts(5))
+        };
+        // The first aggregate is always delivered, then only moves of 2% or more
+        report_as(&mut context, &mut contract, 0, 35000);
+        assert!(delivered());
+        context.block_timestamp(2_000);
+        report_as(&mut context, &mut contract, 0, 35500);
+        assert!(!delivered());
+        context.block_timestamp(3_000);
+        report_as(&mut context, &mut contract, 0, 36000);
+        assert!(delivered());
+        assert_eq!(
+            contract.get_price_update_subscriptions("near".to_string())[0]
+                .last_delivered
+                .unwrap()
+                .0,
+            36000
+        );
+
+        // An unchanged price is redelivered once the heartbeat has passed
+        context.block_timestamp(1_003_000);
+        report_as(&mut context, &mut contract, 0, 36000);
+        assert!(delivered());
+        // 2 mNEAR each for the balance's and the subscription's storage, then
+        // 10 mNEAR notification fee plus 1 mNEAR of callback gas per delivery
+        assert_eq!(
+            contract.get_price_update_balance(accounts(5)),
+            NearToken::from_millinear(963)
+        );
+        assert_eq!(contract.get_reward_pool(), NearToken::from_millinear(33));
+
+        context.predecessor_account_id(accounts(5));
+        testing_env!(context.build());
+        contract.unsubscribe_price_updates("near".to_string());
+        assert_eq!(
+            contract.get_price_update_balance(accounts(5)),
+            NearToken::from_millinear(965)
+        );
+    }
+
+    #[test]
+    fn test_unfunded_price_update_subscriber_is_skipped() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        let subscribe =
+            |context: &mut VMContextBuilder, contract: &mut Oracle, account: AccountId| {
+                context
+                    .predecessor_account_id(account)
+                    .attached_deposit(NearToken::from_millinear(20));
+                testing_env!(context.build());
+                contract.fund_price_updates(None);
+                context.attached_deposit(NearToken::from_yoctonear(0));
+                testing_env!(context.build());
+                contract.subscribe_price_updates("near".to_string(), 100, 0, None);
+            };
+        subscribe(&mut context, &mut contract, accounts(5));
+
+        // Withdrawing the balance leaves the subscription without deliveries
+        context.predecessor_account_id(accounts(5));
+        testing_env!(context.build());
+        contract.withdraw_price_update_balance(NearToken::from_millinear(16));
+        report_as(&mut context, &mut contract, 0, 35000);
+        assert!(!near_sdk::test_utils::get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(5)));
+
+        // and gives up its slot once the asset is full
+        for i in 1..delivery::MAX_PRICE_UPDATE_SUBSCRIPTIONS {
+            subscribe(
+                &mut context,
+                &mut contract,
+                format!("consumer{}.near", i).parse().unwrap(),
+            );
+        }
+        subscribe(&mut context, &mut contract, accounts(4));
+        let subscriptions = contract.get_price_update_subscriptions("near".to_string());
+        assert_eq!(
+            subscriptions.len(),
+            delivery::MAX_PRICE_UPDATE_SUBSCRIPTIONS
+        );
+        assert!(subscriptions.iter().all(|s| s.receiver_id != accounts(5)));
+        assert_eq!(
+            contract.get_price_update_balance(accounts(5)),
+            NearToken::from_millinear(2)
+        );
+    }
+
+    #[test]
+    fn test_price_update_deliveries_share_a_gas_budget() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        let subscribers = [accounts(3), accounts(4), accounts(5)];
+        for subscriber in &subscribers {
+            context
+                .predecessor_account_id(subscriber.clone())
+                .attached_deposit(NearToken::from_millinear(100));
+            testing_env!(context.build());
+            contract.fund_price_updates(None);
+            context.attached_deposit(NearToken::from_yoctonear(0));
+            testing_env!(context.build());
+            contract.subscribe_price_updates(
+                "near".to_string(),
+                100,
+                0,
+                Some(delivery::MAX_CALLBACK_GAS),
+            );
+        }
+        let delivered = || -> Vec<AccountId> {
+            subscribers
+                .iter()
+                .filter(|subscriber| {
+                    near_sdk::test_utils::get_created_receipts()
+                        .iter()
+                        .any(|receipt| &receipt.receiver_id == *subscriber)
+                })
+                .cloned()
+                .collect()
+        };
+
+        // Two 50 TGas callbacks fit the budget; the third stays pending
+        context.block_timestamp(1_000);
+        report_as(&mut context, &mut contract, 0, 35000);
+        assert_eq!(delivered(), vec![accounts(3), accounts(4)]);
+
+        // and goes first on the next aggregate
+        context.block_timestamp(2_000);
+        report_as(&mut context, &mut contract, 0, 36000);
+        assert_eq!(delivered(), vec![accounts(3), accounts(5)]);
+    }
+
+    #[test]
+    fn test_removed_asset_returns_subscription_deposits() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        context
+            .predecessor_account_id(accounts(5))
+            .attached_deposit(NearToken::from_millinear(20));
+        testing_env!(context.build());
+        contract.fund_price_updates(None);
+        context.attached_deposit(NearToken::from_yoctonear(0));
+        testing_env!(context.build());
+        contract.subscribe_price_updates("near".to_string(), 100, 0, None);
+        assert_eq!(
+            contract.get_price_update_balance(accounts(5)),
+            NearToken::from_millinear(16)
+        );
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.remove_asset("near".to_string());
+        assert!(contract
+            .get_price_update_subscriptions("near".to_string())
+            .is_empty());
+        assert_eq!(
+            contract.get_price_update_balance(accounts(5)),
+            NearToken::from_millinear(18)
+        );
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains("price_updates_unsubscribed")));
     }
 
     #[test]
-    fn test_initialization() {
-        let context = get_context(accounts(0));
+    #[should_panic(expected = "Price update balance must cover storage and 10 deliveries")]
+    fn test_unfunded_account_cannot_subscribe() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+        context
+            .predecessor_account_id(accounts(5))
+            .attached_deposit(NearToken::from_millinear(10));
         testing_env!(context.build());
-
-        let contract = Oracle::new(accounts(0), 300_000_000_000, 3);
-        assert_eq!(contract.owner, accounts(0));
-        assert_eq!(contract.recency_threshold, 300_000_000_000);
-        assert_eq!(contract.min_report_count, 3);
-        assert_eq!(contract.attestation_max_age, 600_000_000_000);
-        assert_eq!(contract.admin_timelock_delay, 0);
-        assert_eq!(contract.admin_quorum_bps, 5000);
-        assert_eq!(contract.proposal_counter, 0);
+        contract.fund_price_updates(None);
+        context.attached_deposit(NearToken::from_yoctonear(0));
+        testing_env!(context.build());
+        contract.subscribe_price_updates("near".to_string(), 100, 0, None);
     }
 
     #[test]
-    fn test_add_asset() {
-        let context = get_context(accounts(0));
+    fn test_asset_node_assignment_and_coverage() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 3, 2);
+
+        context.predecessor_account_id(accounts(0));
         testing_env!(context.build());
+        contract.set_asset_nodes("near".to_string(), vec![node(1), node(0)]);
+        assert_eq!(
+            contract.get_asset_nodes("near".to_string()),
+            vec![node(0), node(1)]
+        );
+        assert!(contract.get_node_assets(node(2)).is_empty());
 
-        let mut contract = Oracle::new(accounts(0), 300_000_000_000, 3);
+        // The round closes once both assigned nodes have reported
+        report_as(&mut context, &mut contract, 0, 35000);
+        report_as(&mut context, &mut contract, 1, 35100);
+        assert!(contract.get_open_round("near".to_string()).is_none());
+        assert_eq!(
+            contract.get_price("near".to_string()).unwrap().num_sources,
+            2
+        );
 
-        let asset = Asset {
-            id: "near".to_string(),
-            symbol: "NEAR".to_string(),
-            name: "NEAR Protocol".to_string(),
-            decimals: 4,
-            active: true,
-            min_sources: 3,
-        };
+        let coverage = contract.get_asset_coverage("near".to_string()).unwrap();
+        assert!(coverage.assigned);
+        assert_eq!(coverage.eligible_nodes, vec![node(0), node(1)]);
+        assert_eq!(coverage.reporting_nodes, vec![node(0), node(1)]);
+        assert_eq!(coverage.required_sources, 2);
+    }
 
-        contract.add_asset(asset);
+    #[test]
+    #[should_panic(expected = "Node not assigned to asset")]
+    fn test_unassigned_node_cannot_report() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 3, 2);
 
-        let assets = contract.get_assets();
-        assert_eq!(assets.len(), 1);
-        assert_eq!(assets[0].id, "near");
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_asset_nodes("near".to_string(), vec![node(0), node(1)]);
+        report_as(&mut context, &mut contract, 2, 35000);
     }
 
     #[test]
-    fn test_node_registration_flow() {
+    fn test_rotation_keeps_asset_assignment() {
         let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 2, 2);
+        context.predecessor_account_id(accounts(0));
         testing_env!(context.build());
+        contract.set_asset_nodes("near".to_string(), vec![node(0), node(1)]);
 
-        let mut contract = Oracle::new(accounts(0), 300_000_000_000, 3);
-
-        // Add operator
-        contract.add_node_operator(accounts(1));
-
-        // Approve code hash
-        let code_hash = "test_hash_123".to_string();
-        contract.approve_code_hash(code_hash.clone());
-        contract.approve_attestation(code_hash.clone(), "mr_hash".to_string());
-
-        // Operator sets node account
-        context.predecessor_account_id(accounts(1));
+        context.predecessor_account_id(operator(0));
         testing_env!(context.build());
-        contract.set_node_account(accounts(2));
-
-        // Node registers
-        context.predecessor_account_id(accounts(2));
+        contract.stage_node_rotation(node(0), node(2));
+        context.predecessor_account_id(node(2));
         testing_env!(context.build());
         contract.register_node(
-            code_hash,
-            AttestationData {
-                mr_enclave: "mr_hash".to_string(),
-                issued_at: env::block_timestamp(),
-            },
+            "hash1".to_string(),
+            attestation_for(MR_ENCLAVE, env::block_timestamp()),
+            None,
         );
 
-        // Verify node is authorized
-        assert!(contract.is_authorized(accounts(2)));
+        assert_eq!(
+            contract.get_asset_nodes("near".to_string()),
+            vec![node(1), node(2)]
+        );
+        assert_eq!(contract.get_node_assets(node(2)), vec!["near".to_string()]);
+        report_as(&mut context, &mut contract, 2, 35000);
+        report_as(&mut context, &mut contract, 1, 35100);
+        assert_eq!(
+            contract.get_price("near".to_string()).unwrap().num_sources,
+            2
+        );
     }
 
     #[test]
-    fn test_price_reporting_and_aggregation() {
+    fn test_node_rotation_and_retirement() {
         let mut context = get_context(accounts(0));
-        testing_env!(context.build());
-
-        let mut contract = Oracle::new(accounts(0), 300_000_000_000, 1);
-
-        // Setup
-        let asset = Asset {
-            id: "near".to_string(),
-            symbol: "NEAR".to_string(),
-            name: "NEAR Protocol".to_string(),
-            decimals: 4,
-            active: true,
-            min_sources: 1,
-        };
-        contract.add_asset(asset);
+        let mut contract = setup_with_nodes(&mut context, 2, 2);
+        report_as(&mut context, &mut contract, 0, 35000);
+        report_as(&mut context, &mut contract, 1, 35100);
 
-        // Register two nodes
-        contract.add_node_operator(accounts(1));
-        contract.approve_code_hash("hash1".to_string());
-        contract.approve_attestation("hash1".to_string(), "mr_hash1".to_string());
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.set_max_nodes_per_operator(1);
+        contract.set_node_weight(node(0), 3);
 
-        context.predecessor_account_id(accounts(1));
+        // The staged node joins above the cap while the old one keeps reporting
+        context.predecessor_account_id(operator(0));
         testing_env!(context.build());
-        contract.set_node_account(accounts(2));
+        contract.stage_node_rotation(node(0), node(2));
+        assert_eq!(
+            contract.get_operator_nodes(operator(0)),
+            vec![node(0), node(2)]
+        );
+        assert!(contract.is_authorized(node(0)));
 
-        context.predecessor_account_id(accounts(2));
+        context.predecessor_account_id(node(2));
         testing_env!(context.build());
         contract.register_node(
             "hash1".to_string(),
-            AttestationData {
-                mr_enclave: "mr_hash1".to_string(),
-                issued_at: env::block_timestamp(),
-            },
+            attestation_for(MR_ENCLAVE, env::block_timestamp()),
+            None,
         );
-
-        // Report price from node 1
-        contract.report_price("near".to_string(), 35000, 4); // $3.5000
-
-        // Check price
-        let price_data = contract.get_price("near".to_string());
-        assert!(price_data.is_some());
-        let price = price_data.unwrap();
-        assert_eq!(price.price.multiplier, 35000);
-        assert_eq!(price.price.decimals, 4);
+        assert!(contract.get_pending_rotation(operator(0)).is_none());
+        assert_eq!(contract.get_operator_nodes(operator(0)), vec![node(2)]);
+        assert!(contract.get_node_details(node(0)).is_none());
+        assert_eq!(contract.get_node_details(node(2)).unwrap().weight, 3);
+        assert!(contract.get_price("near".to_string()).is_some());
+
+        // Retiring drops the node and its reports at once
+        context.predecessor_account_id(operator(1));
+        testing_env!(context.build());
+        contract.retire_node(node(1));
+        assert!(!contract.is_authorized(node(1)));
+        assert!(contract.get_operator_nodes(operator(1)).is_empty());
+        assert!(contract.get_price("near".to_string()).is_none());
     }
 
     #[test]
-    fn test_price_requires_minimum_sources() {
+    fn test_operator_fleet_counts_once() {
         let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 2, 2);
+
+        context.predecessor_account_id(accounts(0));
         testing_env!(context.build());
+        contract.set_max_nodes_per_operator(2);
 
-        let mut contract = Oracle::new(accounts(0), 300_000_000_000, 2);
+        // Operator 0 adds a second node
+        context.predecessor_account_id(operator(0));
+        testing_env!(context.build());
+        contract.set_node_account(node(2));
+        context.predecessor_account_id(node(2));
+        testing_env!(context.build());
+        contract.register_node(
+            "hash1".to_string(),
+            attestation_for(MR_ENCLAVE, env::block_timestamp()),
+            None,
+        );
+        assert_eq!(
+            contract.get_operator_nodes(operator(0)),
+            vec![node(0), node(2)]
+        );
+        assert_eq!(contract.get_operator_fleet(operator(0)).len(), 2);
 
-        let asset = Asset {
-            id: "near".to_string(),
-            symbol: "NEAR".to_string(),
-            name: "NEAR Protocol".to_string(),
-            decimals: 4,
-            active: true,
-            min_sources: 2,
-        };
-        contract.add_asset(asset);
+        // Both of operator 0's nodes count as one source, its latest report
+        report_as(&mut context, &mut contract, 0, 35000);
+        context.block_timestamp(10);
+        report_as(&mut context, &mut contract, 2, 36000);
+        assert!(contract.get_price("near".to_string()).is_none());
+        context.block_timestamp(20);
+        report_as(&mut context, &mut contract, 1, 35100);
+        let price = contract.get_price("near".to_string()).unwrap();
+        assert_eq!(price.num_sources, 2);
+        assert_eq!(price.price.multiplier, 35550);
+    }
 
-        contract.add_node_operator(accounts(1));
-        contract.add_node_operator(accounts(2));
-        contract.approve_code_hash("hash1".to_string());
-        contract.approve_attestation("hash1".to_string(), "mr_hash1".to_string());
+    #[test]
+    #[should_panic(expected = "Operator node limit reached")]
+    fn test_operator_node_limit_enforced() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 2, 2);
 
-        context.predecessor_account_id(accounts(1));
-        testing_env!(context.build());
-        contract.set_node_account(accounts(3));
-        context.predecessor_account_id(accounts(2));
+        context.predecessor_account_id(accounts(0));
         testing_env!(context.build());
-        contract.set_node_account(accounts(4));
+        contract.set_max_nodes_per_operator(2);
 
-        context.predecessor_account_id(accounts(3));
+        // Operator 0 adds a second node
+        context.predecessor_account_id(operator(0));
         testing_env!(context.build());
-        contract.register_node(
-            "hash1".to_string(),
-            AttestationData {
-                mr_enclave: "mr_hash1".to_string(),
-                issued_at: env::block_timestamp(),
-            },
-        );
-        context.predecessor_account_id(accounts(4));
+        contract.set_node_account(node(2));
+        context.predecessor_account_id(node(2));
         testing_env!(context.build());
         contract.register_node(
             "hash1".to_string(),
-            AttestationData {
-                mr_enclave: "mr_hash1".to_string(),
-                issued_at: env::block_timestamp(),
-            },
+            attestation_for(MR_ENCLAVE, env::block_timestamp()),
+            None,
         );
 
-        // Only one report -> should not set aggregated price
-        context.predecessor_account_id(accounts(3));
+        // A third node is over the limit
+        context.predecessor_account_id(operator(0));
         testing_env!(context.build());
-        contract.report_price("near".to_string(), 35000, 4);
-        assert!(contract.get_price("near".to_string()).is_none());
+        contract.set_node_account(node(3));
+    }
 
-        // Second node reports -> aggregation allowed
-        context.predecessor_account_id(accounts(4));
+    #[test]
+    #[should_panic(expected = "Delisted assets cannot be relisted")]
+    fn test_delisted_asset_cannot_be_relisted() {
+        let context = get_context(accounts(0));
         testing_env!(context.build());
-        contract.report_price("near".to_string(), 36000, 4);
-        let price = contract
-            .get_price("near".to_string())
-            .expect("price available");
-        assert_eq!(price.num_sources, 2);
-        assert_eq!(price.price.multiplier, 35500); // median of 35000 and 36000
+
+        let mut contract = Oracle::new(accounts(0), 300_000_000_000, 1);
+        contract.add_asset(near_asset(1));
+        contract.set_asset_status("near".to_string(), AssetStatus::Delisted);
+        contract.update_asset(near_asset(1));
     }
 
     #[test]
@@ -1005,19 +5638,11 @@ mod tests {
 
         let mut contract = Oracle::new(accounts(0), 100, 1);
 
-        let asset = Asset {
-            id: "near".to_string(),
-            symbol: "NEAR".to_string(),
-            name: "NEAR Protocol".to_string(),
-            decimals: 4,
-            active: true,
-            min_sources: 1,
-        };
-        contract.add_asset(asset);
+        contract.add_asset(near_asset(1));
 
         contract.add_node_operator(accounts(1));
         contract.approve_code_hash("hash1".to_string());
-        contract.approve_attestation("hash1".to_string(), "mr_hash1".to_string());
+        approve_test_enclave(&mut contract, "hash1");
 
         context.predecessor_account_id(accounts(1));
         testing_env!(context.build());
@@ -1028,10 +5653,8 @@ mod tests {
         testing_env!(context.build());
         contract.register_node(
             "hash1".to_string(),
-            AttestationData {
-                mr_enclave: "mr_hash1".to_string(),
-                issued_at: env::block_timestamp(),
-            },
+            attestation_for(MR_ENCLAVE, env::block_timestamp()),
+            None,
         );
         contract.report_price("near".to_string(), 35000, 4);
 
@@ -1052,7 +5675,7 @@ mod tests {
         let mut contract = Oracle::new(accounts(0), 300_000_000_000, 1);
         contract.add_node_operator(accounts(1));
         contract.approve_code_hash("hash1".to_string());
-        contract.approve_attestation("hash1".to_string(), "mr_hash1".to_string());
+        approve_test_enclave(&mut contract, "hash1");
 
         context.predecessor_account_id(accounts(1));
         testing_env!(context.build());
@@ -1062,11 +5685,212 @@ mod tests {
         testing_env!(context.build());
         contract.register_node(
             "hash1".to_string(),
-            AttestationData {
-                mr_enclave: "wrong_hash".to_string(),
-                issued_at: env::block_timestamp(),
-            },
+            attestation_for([0x22; 32], env::block_timestamp()),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Attestation is not bound to the signing key")]
+    fn test_register_node_rejects_quote_for_other_key() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 0, 1);
+        contract.add_node_operator(operator(0));
+
+        context.predecessor_account_id(operator(0));
+        testing_env!(context.build());
+        contract.set_node_account(node(0));
+
+        // Quote bound to the default signer key, submitted with another key
+        let attestation = attestation_for(MR_ENCLAVE, 0);
+        context.predecessor_account_id(node(0)).signer_account_pk(
+            "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp"
+                .parse()
+                .unwrap(),
+        );
+        testing_env!(context.build());
+        contract.register_node("hash1".to_string(), attestation, None);
+    }
+
+    #[test]
+    fn test_register_node_rejects_forged_quotes() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 0, 1);
+        let approved_roots = &contract.approved_root_certs;
+        let verify = |quote: &[u8], collateral: &AttestationCollateral| {
+            attestation::verify_quote(quote, approved_roots, collateral, 0)
+                .err()
+                .unwrap()
+        };
+
+        // Measurement altered after signing
+        let mut quote = attestation_for(MR_ENCLAVE, 0).quote.0;
+        quote[48 + 64] ^= 1;
+        assert_eq!(
+            verify(&quote, &AttestationCollateral::default()),
+            "Invalid quote signature"
+        );
+
+        // PCK certificate on the revocation list
+        let quote = attestation_for(MR_ENCLAVE, 0).quote.0;
+        let collateral = AttestationCollateral {
+            revoked_serials: vec!["02".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(verify(&quote, &collateral), "Certificate has been revoked");
+
+        // PCE below the minimum; v4 quotes carry no PCE SVN in the header
+        let collateral = AttestationCollateral {
+            min_pce_svn: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            verify(&quote, &collateral),
+            "PCE security version below minimum"
+        );
+        let report_data = report_data_for(&env::signer_account_pk(), 0);
+        let tdx_quote = attestation::test_utils::tdx_quote([0x33; 48], false, report_data);
+        assert!(attestation::verify_quote(&tdx_quote, approved_roots, &collateral, 0).is_ok());
+
+        // Root certificate no longer trusted
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        let root_hash = contract.get_root_certificates()[0];
+        contract.remove_root_certificate(root_hash);
+        assert_eq!(
+            attestation::verify_quote(
+                &quote,
+                &contract.approved_root_certs,
+                &AttestationCollateral::default(),
+                0
+            )
+            .err()
+            .unwrap(),
+            "Root certificate not approved"
+        );
+    }
+
+    #[test]
+    fn test_register_node_rejects_debug_enclaves() {
+        let mut context = get_context(accounts(0));
+        let contract = setup_with_nodes(&mut context, 0, 1);
+        let verify = |quote: &[u8]| {
+            attestation::verify_quote(
+                quote,
+                &contract.approved_root_certs,
+                &AttestationCollateral::default(),
+                0,
+            )
+        };
+        let report_data = report_data_for(&env::signer_account_pk(), 0);
+
+        let quote = attestation::test_utils::sgx_debug_quote(MR_ENCLAVE, report_data);
+        assert_eq!(
+            verify(&quote).err().unwrap(),
+            "Debug enclaves are not accepted"
+        );
+
+        let quote = attestation::test_utils::tdx_quote([0x33; 48], true, report_data);
+        assert_eq!(
+            verify(&quote).err().unwrap(),
+            "Debug enclaves are not accepted"
+        );
+        let quote = attestation::test_utils::tdx_quote([0x33; 48], false, report_data);
+        assert_eq!(verify(&quote).unwrap().measurement, vec![0x33; 48]);
+    }
+
+    #[test]
+    fn test_signed_report_relayed() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+
+        // The node re-registers with a key generated inside its enclave
+        let enclave_key = SigningKey::from_bytes(&[7; 32]);
+        let signing_key = PublicKey::from_parts(
+            CurveType::ED25519,
+            enclave_key.verifying_key().to_bytes().to_vec(),
+        )
+        .unwrap();
+        contract.register_node(
+            "hash1".to_string(),
+            attestation_bound_to(&signing_key, MR_ENCLAVE, 0),
+            Some(signing_key),
+        );
+
+        let payload = SignedPriceReport {
+            contract_id: env::current_account_id(),
+            node_account: node(0),
+            nonce: 1,
+            signed_at: 0,
+            prices: vec![PriceInput {
+                asset_id: "near".to_string(),
+                multiplier: 35000,
+                decimals: 4,
+            }],
+        };
+        let signature = enclave_key
+            .sign(&borsh::to_vec(&payload).unwrap())
+            .to_bytes()
+            .to_vec();
+
+        context.predecessor_account_id(accounts(4));
+        testing_env!(context.build());
+        let result = contract.report_price_signed(payload, signature.into());
+        assert_eq!(result.accepted, vec!["near".to_string()]);
+        assert_eq!(
+            contract
+                .get_price("near".to_string())
+                .unwrap()
+                .price
+                .multiplier,
+            35000
+        );
+        assert_eq!(contract.get_node_details(node(0)).unwrap().last_nonce, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Report nonce already used")]
+    fn test_signed_report_replay_rejected() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_with_nodes(&mut context, 1, 1);
+
+        // The node re-registers with a key generated inside its enclave
+        let enclave_key = SigningKey::from_bytes(&[7; 32]);
+        let signing_key = PublicKey::from_parts(
+            CurveType::ED25519,
+            enclave_key.verifying_key().to_bytes().to_vec(),
+        )
+        .unwrap();
+        contract.register_node(
+            "hash1".to_string(),
+            attestation_bound_to(&signing_key, MR_ENCLAVE, 0),
+            Some(signing_key),
         );
+
+        let payload = SignedPriceReport {
+            contract_id: env::current_account_id(),
+            node_account: node(0),
+            nonce: 1,
+            signed_at: 0,
+            prices: vec![PriceInput {
+                asset_id: "near".to_string(),
+                multiplier: 35000,
+                decimals: 4,
+            }],
+        };
+        let signature = enclave_key
+            .sign(&borsh::to_vec(&payload).unwrap())
+            .to_bytes()
+            .to_vec();
+
+        context.predecessor_account_id(accounts(4));
+        testing_env!(context.build());
+        contract.report_price_signed(payload.clone(), signature.clone().into());
+        contract.report_price_signed(payload, signature.into());
     }
 
     #[test]
@@ -1079,7 +5903,28 @@ mod tests {
         contract.attestation_max_age = 100;
         contract.add_node_operator(accounts(1));
         contract.approve_code_hash("hash1".to_string());
-        contract.approve_attestation("hash1".to_string(), "mr_hash1".to_string());
+        approve_test_enclave(&mut contract, "hash1");
+
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        contract.set_node_account(accounts(2));
+
+        context.predecessor_account_id(accounts(2));
+        context.block_timestamp(1_000);
+        testing_env!(context.build());
+        contract.register_node("hash1".to_string(), attestation_for(MR_ENCLAVE, 0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attestation issued in the future")]
+    fn test_register_node_rejects_future_attestation() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = Oracle::new(accounts(0), 300_000_000_000, 1);
+        contract.add_node_operator(accounts(1));
+        contract.approve_code_hash("hash1".to_string());
+        approve_test_enclave(&mut contract, "hash1");
 
         context.predecessor_account_id(accounts(1));
         testing_env!(context.build());
@@ -1090,10 +5935,8 @@ mod tests {
         testing_env!(context.build());
         contract.register_node(
             "hash1".to_string(),
-            AttestationData {
-                mr_enclave: "mr_hash1".to_string(),
-                issued_at: 0,
-            },
+            attestation_for(MR_ENCLAVE, u64::MAX),
+            None,
         );
     }
 
@@ -1105,19 +5948,11 @@ mod tests {
 
         let mut contract = Oracle::new(accounts(0), 300_000_000_000, 1);
 
-        let asset = Asset {
-            id: "near".to_string(),
-            symbol: "NEAR".to_string(),
-            name: "NEAR Protocol".to_string(),
-            decimals: 4,
-            active: true,
-            min_sources: 1,
-        };
-        contract.add_asset(asset);
+        contract.add_asset(near_asset(1));
 
         contract.add_node_operator(accounts(1));
         contract.approve_code_hash("hash1".to_string());
-        contract.approve_attestation("hash1".to_string(), "mr_hash1".to_string());
+        approve_test_enclave(&mut contract, "hash1");
 
         context.predecessor_account_id(accounts(1));
         testing_env!(context.build());
@@ -1127,10 +5962,8 @@ mod tests {
         testing_env!(context.build());
         contract.register_node(
             "hash1".to_string(),
-            AttestationData {
-                mr_enclave: "mr_hash1".to_string(),
-                issued_at: env::block_timestamp(),
-            },
+            attestation_for(MR_ENCLAVE, env::block_timestamp()),
+            None,
         );
 
         contract.report_price("near".to_string(), 35000, 2);
@@ -1176,15 +6009,7 @@ mod tests {
 
         let mut contract = Oracle::new(accounts(0), 300_000_000_000, 3);
 
-        let asset = Asset {
-            id: "near".to_string(),
-            symbol: "NEAR".to_string(),
-            name: "NEAR Protocol".to_string(),
-            decimals: 4,
-            active: true,
-            min_sources: 3,
-        };
-        contract.add_asset(asset);
+        contract.add_asset(near_asset(3));
 
         // Try to report without being authorized
         contract.report_price("near".to_string(), 35000, 4);
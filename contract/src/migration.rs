@@ -0,0 +1,670 @@
+use crate::*;
+
+// Storage key holding the layout version of the serialized `Oracle` state.
+// Deployments created before versioning was introduced have no such key.
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+
+pub const STATE_VERSION: u16 = 3;
+
+pub fn read_state_version() -> u16 {
+    env::storage_read(STATE_VERSION_KEY)
+        .map(|bytes| u16::try_from_slice(&bytes).expect("Invalid state version"))
+        .unwrap_or(1)
+}
+
+pub fn write_state_version(version: u16) {
+    env::storage_write(STATE_VERSION_KEY, &borsh::to_vec(&version).unwrap());
+}
+
+// Layout of the initial release, before confidence, rounds, history and the
+// other per-price metadata were added.
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct PriceV1 {
+    pub multiplier: u128,
+    pub decimals: u8,
+    pub timestamp: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct PriceReportV1 {
+    pub oracle_id: AccountId,
+    pub price: PriceV1,
+    pub timestamp: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AssetV1 {
+    pub id: String,
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+    pub active: bool,
+    pub min_sources: u8,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OracleNodeV1 {
+    pub account_id: AccountId,
+    pub operator_id: AccountId,
+    pub registered_at: u64,
+    pub code_hash: String,
+    pub last_report: u64,
+    pub active: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum AdminActionV1 {
+    AddNodeOperator {
+        account_id: AccountId,
+    },
+    RemoveNodeOperator {
+        account_id: AccountId,
+    },
+    ApproveCodeHash {
+        code_hash: String,
+    },
+    RemoveCodeHash {
+        code_hash: String,
+    },
+    ApproveAttestation {
+        code_hash: String,
+        mr_enclave: String,
+    },
+    RemoveAttestation {
+        code_hash: String,
+    },
+    Pause,
+    Resume,
+    UpdateConfig {
+        recency_threshold: Option<u64>,
+        min_report_count: Option<u8>,
+    },
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AdminProposalV1 {
+    pub id: u64,
+    pub proposer: AccountId,
+    pub action: AdminActionV1,
+    pub scheduled_for: u64,
+    pub approvals: Vec<AccountId>,
+    pub executed: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OracleV1 {
+    pub owner: AccountId,
+    pub paused: bool,
+    pub assets: UnorderedMap<String, AssetV1>,
+    pub asset_list: Vector<String>,
+    pub price_reports: LookupMap<String, Vec<PriceReportV1>>,
+    pub aggregated_prices: LookupMap<String, PriceV1>,
+    pub authorized_nodes: UnorderedSet<AccountId>,
+    pub node_details: LookupMap<AccountId, OracleNodeV1>,
+    pub whitelisted_operators: UnorderedSet<AccountId>,
+    pub approved_code_hashes: UnorderedSet<String>,
+    pub approved_enclaves: LookupMap<String, String>,
+    pub operator_to_node: LookupMap<AccountId, AccountId>,
+    pub node_to_operator: LookupMap<AccountId, AccountId>,
+    pub recency_threshold: u64,
+    pub min_report_count: u8,
+    pub attestation_max_age: u64,
+    pub admin_proposers: UnorderedSet<AccountId>,
+    pub admin_voters: UnorderedSet<AccountId>,
+    pub admin_timelock_delay: u64,
+    pub admin_quorum_bps: u16,
+    pub proposals: UnorderedMap<u64, AdminProposalV1>,
+    pub proposal_counter: u64,
+}
+
+// Layout of v2, before asset statuses, attestation quotes, node fleets,
+// staking, fees and price delivery were added.
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum AggregationMethodV2 {
+    Median,
+    TrimmedMean { trim_bps: u16 },
+    WeightedMedian,
+    Twap { window: u64 },
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AssetV2 {
+    pub id: String,
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+    pub active: bool,
+    pub min_sources: u8,
+    pub max_deviation_bps: u16,
+    pub aggregation: AggregationMethodV2,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OracleNodeV2 {
+    pub account_id: AccountId,
+    pub operator_id: AccountId,
+    pub registered_at: u64,
+    pub code_hash: String,
+    pub last_report: u64,
+    pub active: bool,
+    pub outlier_reports: u64,
+    pub weight: u32,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum AdminActionV2 {
+    AddNodeOperator {
+        account_id: AccountId,
+    },
+    RemoveNodeOperator {
+        account_id: AccountId,
+    },
+    ApproveCodeHash {
+        code_hash: String,
+    },
+    RemoveCodeHash {
+        code_hash: String,
+    },
+    ApproveAttestation {
+        code_hash: String,
+        mr_enclave: String,
+    },
+    RemoveAttestation {
+        code_hash: String,
+    },
+    Pause,
+    Resume,
+    UpdateConfig {
+        recency_threshold: Option<u64>,
+        min_report_count: Option<u8>,
+        history_depth: Option<u32>,
+        ema_period: Option<u64>,
+        round_duration: Option<u64>,
+    },
+    SetAggregationMethod {
+        asset_id: String,
+        method: AggregationMethodV2,
+    },
+    SetNodeWeight {
+        account_id: AccountId,
+        weight: u32,
+    },
+    UpgradeContract {
+        code: Base64VecU8,
+    },
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AdminProposalV2 {
+    pub id: u64,
+    pub proposer: AccountId,
+    pub action: AdminActionV2,
+    pub scheduled_for: u64,
+    pub approvals: Vec<AccountId>,
+    pub executed: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OracleV2 {
+    pub owner: AccountId,
+    pub paused: bool,
+    pub assets: UnorderedMap<String, AssetV2>,
+    pub asset_list: Vector<String>,
+    pub price_reports: LookupMap<String, Vec<PriceReport>>,
+    pub aggregated_prices: LookupMap<String, Price>,
+    pub authorized_nodes: UnorderedSet<AccountId>,
+    pub node_details: LookupMap<AccountId, OracleNodeV2>,
+    pub whitelisted_operators: UnorderedSet<AccountId>,
+    pub approved_code_hashes: UnorderedSet<String>,
+    pub approved_enclaves: LookupMap<String, String>,
+    pub operator_to_node: LookupMap<AccountId, AccountId>,
+    pub node_to_operator: LookupMap<AccountId, AccountId>,
+    pub recency_threshold: u64,
+    pub min_report_count: u8,
+    pub attestation_max_age: u64,
+    pub admin_proposers: UnorderedSet<AccountId>,
+    pub admin_voters: UnorderedSet<AccountId>,
+    pub admin_timelock_delay: u64,
+    pub admin_quorum_bps: u16,
+    pub proposals: UnorderedMap<u64, AdminProposalV2>,
+    pub proposal_counter: u64,
+    pub twap_samples: LookupMap<String, Vec<Price>>,
+    pub price_history: LookupMap<(String, u64), HistoryEntry>,
+    pub history_cursors: LookupMap<String, HistoryCursor>,
+    pub history_depth: u32,
+    pub price_accumulators: LookupMap<String, PriceAccumulator>,
+    pub ema_period: u64,
+    pub open_rounds: LookupMap<String, PriceRound>,
+    pub round_counters: LookupMap<String, u64>,
+    pub round_duration: u64,
+}
+
+// Before v3, inactive assets were still live feeds; freeze them rather than
+// delist, which can't be undone
+fn asset_status(active: bool) -> AssetStatus {
+    if active {
+        AssetStatus::Active
+    } else {
+        AssetStatus::QueryFrozen
+    }
+}
+
+impl From<AggregationMethodV2> for AggregationMethod {
+    fn from(method: AggregationMethodV2) -> Self {
+        match method {
+            AggregationMethodV2::Median => AggregationMethod::Median,
+            AggregationMethodV2::TrimmedMean { trim_bps } => {
+                AggregationMethod::TrimmedMean { trim_bps }
+            }
+            AggregationMethodV2::WeightedMedian => AggregationMethod::WeightedMedian,
+            AggregationMethodV2::Twap { window } => AggregationMethod::Twap { window },
+        }
+    }
+}
+
+impl From<PriceV1> for Price {
+    fn from(price: PriceV1) -> Self {
+        Self {
+            multiplier: price.multiplier,
+            decimals: price.decimals,
+            timestamp: price.timestamp,
+            conf: 0,
+            round_id: 0,
+        }
+    }
+}
+
+impl From<PriceReportV1> for PriceReport {
+    fn from(report: PriceReportV1) -> Self {
+        Self {
+            oracle_id: report.oracle_id,
+            price: report.price.into(),
+            timestamp: report.timestamp,
+            flagged: false,
+        }
+    }
+}
+
+impl From<AssetV1> for Asset {
+    fn from(asset: AssetV1) -> Self {
+        Self {
+            id: asset.id,
+            symbol: asset.symbol,
+            name: asset.name,
+            decimals: asset.decimals,
+            status: asset_status(asset.active),
+            min_sources: asset.min_sources,
+            max_deviation_bps: 0,
+            aggregation: AggregationMethod::Median,
+            round_quorum: 0,
+        }
+    }
+}
+
+impl From<AssetV2> for Asset {
+    fn from(asset: AssetV2) -> Self {
+        Self {
+            id: asset.id,
+            symbol: asset.symbol,
+            name: asset.name,
+            decimals: asset.decimals,
+            status: asset_status(asset.active),
+            min_sources: asset.min_sources,
+            max_deviation_bps: asset.max_deviation_bps,
+            aggregation: asset.aggregation.into(),
+            round_quorum: 0,
+        }
+    }
+}
+
+impl From<OracleNodeV1> for OracleNode {
+    fn from(node: OracleNodeV1) -> Self {
+        OracleNodeV2 {
+            account_id: node.account_id,
+            operator_id: node.operator_id,
+            registered_at: node.registered_at,
+            code_hash: node.code_hash,
+            last_report: node.last_report,
+            active: node.active,
+            outlier_reports: 0,
+            weight: 1,
+        }
+        .into()
+    }
+}
+
+// Nodes from before quotes were verified have no measurement on record and
+// were never attested in the current sense. They count as attested at
+// migration, so they get one reattestation window to submit a quote, and
+// `migrate_nodes` fills in the measurement their code hash was approved with.
+impl From<OracleNodeV2> for OracleNode {
+    fn from(node: OracleNodeV2) -> Self {
+        Self {
+            account_id: node.account_id,
+            operator_id: node.operator_id,
+            registered_at: node.registered_at,
+            code_hash: node.code_hash,
+            last_report: node.last_report,
+            active: node.active,
+            outlier_reports: node.outlier_reports,
+            weight: node.weight,
+            suspended_until: 0,
+            consecutive_outliers: 0,
+            signing_key: None,
+            last_nonce: 0,
+            attested_at: env::block_timestamp(),
+            measurement: String::new(),
+            reputation: NodeReputation::default(),
+        }
+    }
+}
+
+impl From<AdminActionV1> for AdminAction {
+    fn from(action: AdminActionV1) -> Self {
+        match action {
+            AdminActionV1::AddNodeOperator { account_id } => {
+                AdminAction::AddNodeOperator { account_id }
+            }
+            AdminActionV1::RemoveNodeOperator { account_id } => {
+                AdminAction::RemoveNodeOperator { account_id }
+            }
+            AdminActionV1::ApproveCodeHash { code_hash } => {
+                AdminAction::ApproveCodeHash { code_hash }
+            }
+            AdminActionV1::RemoveCodeHash { code_hash } => {
+                AdminAction::RemoveCodeHash { code_hash }
+            }
+            AdminActionV1::ApproveAttestation {
+                code_hash,
+                mr_enclave,
+            } => AdminAction::ApproveAttestation {
+                code_hash,
+                mr_enclave,
+            },
+            AdminActionV1::RemoveAttestation { code_hash } => {
+                AdminAction::RemoveAttestation { code_hash }
+            }
+            AdminActionV1::Pause => AdminAction::Pause,
+            AdminActionV1::Resume => AdminAction::Resume,
+            AdminActionV1::UpdateConfig {
+                recency_threshold,
+                min_report_count,
+            } => AdminAction::UpdateConfig {
+                recency_threshold,
+                min_report_count,
+                history_depth: None,
+                ema_period: None,
+                round_duration: None,
+            },
+        }
+    }
+}
+
+impl From<AdminActionV2> for AdminAction {
+    fn from(action: AdminActionV2) -> Self {
+        match action {
+            AdminActionV2::AddNodeOperator { account_id } => {
+                AdminAction::AddNodeOperator { account_id }
+            }
+            AdminActionV2::RemoveNodeOperator { account_id } => {
+                AdminAction::RemoveNodeOperator { account_id }
+            }
+            AdminActionV2::ApproveCodeHash { code_hash } => {
+                AdminAction::ApproveCodeHash { code_hash }
+            }
+            AdminActionV2::RemoveCodeHash { code_hash } => {
+                AdminAction::RemoveCodeHash { code_hash }
+            }
+            AdminActionV2::ApproveAttestation {
+                code_hash,
+                mr_enclave,
+            } => AdminAction::ApproveAttestation {
+                code_hash,
+                mr_enclave,
+            },
+            AdminActionV2::RemoveAttestation { code_hash } => {
+                AdminAction::RemoveAttestation { code_hash }
+            }
+            AdminActionV2::Pause => AdminAction::Pause,
+            AdminActionV2::Resume => AdminAction::Resume,
+            AdminActionV2::UpdateConfig {
+                recency_threshold,
+                min_report_count,
+                history_depth,
+                ema_period,
+                round_duration,
+            } => AdminAction::UpdateConfig {
+                recency_threshold,
+                min_report_count,
+                history_depth,
+                ema_period,
+                round_duration,
+            },
+            AdminActionV2::SetAggregationMethod { asset_id, method } => {
+                AdminAction::SetAggregationMethod {
+                    asset_id,
+                    method: method.into(),
+                }
+            }
+            AdminActionV2::SetNodeWeight { account_id, weight } => {
+                AdminAction::SetNodeWeight { account_id, weight }
+            }
+            AdminActionV2::UpgradeContract { code } => AdminAction::UpgradeContract { code },
+        }
+    }
+}
+
+impl From<AdminProposalV1> for AdminProposal {
+    fn from(proposal: AdminProposalV1) -> Self {
+        Self {
+            id: proposal.id,
+            proposer: proposal.proposer,
+            action: proposal.action.into(),
+            scheduled_for: proposal.scheduled_for,
+            approvals: proposal.approvals,
+            executed: proposal.executed,
+        }
+    }
+}
+
+impl From<AdminProposalV2> for AdminProposal {
+    fn from(proposal: AdminProposalV2) -> Self {
+        Self {
+            id: proposal.id,
+            proposer: proposal.proposer,
+            action: proposal.action.into(),
+            scheduled_for: proposal.scheduled_for,
+            approvals: proposal.approvals,
+            executed: proposal.executed,
+        }
+    }
+}
+
+// Re-encodes every stored value whose type changed since v1. Old values are
+// removed before the new encoding is written, since inserting into a map
+// decodes the previous value. Collections that kept their element type are
+// carried over as-is; new ones start empty.
+pub fn migrate_from_v1(mut old: OracleV1) -> Oracle {
+    let mut state = Oracle::new(
+        old.owner.clone(),
+        old.recency_threshold,
+        old.min_report_count,
+    );
+
+    let assets: Vec<(String, AssetV1)> = old.assets.iter().collect();
+    old.assets.clear();
+    for (asset_id, asset) in assets {
+        if let Some(reports) = old.price_reports.remove(&asset_id) {
+            let reports: Vec<PriceReport> = reports.into_iter().map(PriceReport::from).collect();
+            state.price_reports.insert(&asset_id, &reports);
+        }
+        if let Some(price) = old.aggregated_prices.remove(&asset_id) {
+            state
+                .aggregated_prices
+                .insert(&asset_id, &Price::from(price));
+        }
+        state.assets.insert(&asset_id, &Asset::from(asset));
+    }
+
+    migrate_nodes(
+        &mut state,
+        &old.authorized_nodes,
+        &mut old.node_details,
+        &old.approved_enclaves,
+    );
+
+    let proposals: Vec<(u64, AdminProposalV1)> = old.proposals.iter().collect();
+    old.proposals.clear();
+    for (proposal_id, proposal) in proposals {
+        state
+            .proposals
+            .insert(&proposal_id, &AdminProposal::from(proposal));
+    }
+
+    state.paused = old.paused;
+    state.asset_list = old.asset_list;
+    state.authorized_nodes = old.authorized_nodes;
+    migrate_operator_nodes(
+        &mut state,
+        &old.whitelisted_operators,
+        &mut old.operator_to_node,
+    );
+    state.whitelisted_operators = old.whitelisted_operators;
+    migrate_enclaves(
+        &mut state,
+        &old.approved_code_hashes,
+        &mut old.approved_enclaves,
+    );
+    state.approved_code_hashes = old.approved_code_hashes;
+    state.node_to_operator = old.node_to_operator;
+    state.attestation_max_age = old.attestation_max_age;
+    state.admin_proposers = old.admin_proposers;
+    state.admin_voters = old.admin_voters;
+    state.admin_timelock_delay = old.admin_timelock_delay;
+    state.admin_quorum_bps = old.admin_quorum_bps;
+    state.proposal_counter = old.proposal_counter;
+    state
+}
+
+// Same as `migrate_from_v1` for the v2 layout. Reports, prices, history,
+// accumulators and rounds kept their types and are carried over as-is.
+pub fn migrate_from_v2(mut old: OracleV2) -> Oracle {
+    let mut state = Oracle::new(
+        old.owner.clone(),
+        old.recency_threshold,
+        old.min_report_count,
+    );
+
+    let assets: Vec<(String, AssetV2)> = old.assets.iter().collect();
+    old.assets.clear();
+    for (asset_id, asset) in assets {
+        state.assets.insert(&asset_id, &Asset::from(asset));
+    }
+
+    migrate_nodes(
+        &mut state,
+        &old.authorized_nodes,
+        &mut old.node_details,
+        &old.approved_enclaves,
+    );
+
+    let proposals: Vec<(u64, AdminProposalV2)> = old.proposals.iter().collect();
+    old.proposals.clear();
+    for (proposal_id, proposal) in proposals {
+        state
+            .proposals
+            .insert(&proposal_id, &AdminProposal::from(proposal));
+    }
+
+    state.paused = old.paused;
+    state.asset_list = old.asset_list;
+    state.price_reports = old.price_reports;
+    state.aggregated_prices = old.aggregated_prices;
+    state.authorized_nodes = old.authorized_nodes;
+    migrate_operator_nodes(
+        &mut state,
+        &old.whitelisted_operators,
+        &mut old.operator_to_node,
+    );
+    state.whitelisted_operators = old.whitelisted_operators;
+    migrate_enclaves(
+        &mut state,
+        &old.approved_code_hashes,
+        &mut old.approved_enclaves,
+    );
+    state.approved_code_hashes = old.approved_code_hashes;
+    state.node_to_operator = old.node_to_operator;
+    state.attestation_max_age = old.attestation_max_age;
+    state.admin_proposers = old.admin_proposers;
+    state.admin_voters = old.admin_voters;
+    state.admin_timelock_delay = old.admin_timelock_delay;
+    state.admin_quorum_bps = old.admin_quorum_bps;
+    state.proposal_counter = old.proposal_counter;
+    state.twap_samples = old.twap_samples;
+    state.price_history = old.price_history;
+    state.history_cursors = old.history_cursors;
+    state.history_depth = old.history_depth;
+    state.price_accumulators = old.price_accumulators;
+    state.ema_period = old.ema_period;
+    state.open_rounds = old.open_rounds;
+    state.round_counters = old.round_counters;
+    state.round_duration = old.round_duration;
+    state
+}
+
+// Re-encodes node details. Each node is recorded with the measurement its
+// code hash was approved with, which is what it registered against unless
+// the approval was replaced since, so `remove_enclave_measurement` reaches it.
+fn migrate_nodes<N: BorshDeserialize + BorshSerialize + Into<OracleNode>>(
+    state: &mut Oracle,
+    authorized_nodes: &UnorderedSet<AccountId>,
+    node_details: &mut LookupMap<AccountId, N>,
+    approved_enclaves: &LookupMap<String, String>,
+) {
+    for node_account in authorized_nodes.iter() {
+        if let Some(node) = node_details.remove(&node_account) {
+            let mut node: OracleNode = node.into();
+            node.measurement = approved_enclaves
+                .get(&node.code_hash)
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            state.node_details.insert(&node_account, &node);
+        }
+    }
+}
+
+// Each operator's node becomes a one-node fleet under the same prefix
+fn migrate_operator_nodes(
+    state: &mut Oracle,
+    whitelisted_operators: &UnorderedSet<AccountId>,
+    operator_to_node: &mut LookupMap<AccountId, AccountId>,
+) {
+    for operator_id in whitelisted_operators.iter() {
+        if let Some(node_account) = operator_to_node.remove(&operator_id) {
+            state
+                .operator_nodes
+                .insert(&operator_id, &vec![node_account]);
+        }
+    }
+}
+
+// Single measurements become one-entry policies under the same prefix
+fn migrate_enclaves(
+    state: &mut Oracle,
+    approved_code_hashes: &UnorderedSet<String>,
+    approved_enclaves: &mut LookupMap<String, String>,
+) {
+    for code_hash in approved_code_hashes.iter() {
+        if let Some(mr_enclave) = approved_enclaves.remove(&code_hash) {
+            state.approved_enclaves.insert(
+                &code_hash,
+                &EnclavePolicy {
+                    measurements: vec![mr_enclave.to_ascii_lowercase()],
+                    signer: SignerPolicy::default(),
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use crate::*;
+use near_sdk::PromiseError;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeePolicy {
+    /// Charged per `pull_price` call to consumers without a subscription.
+    /// Zero makes pulls free.
+    pub price_fee: NearToken,
+    /// Price of one subscription period. Zero disables subscriptions.
+    pub subscription_fee: NearToken,
+    /// Length of one subscription period, in nanoseconds.
+    pub subscription_period: u64,
+    /// Charged to a price update subscriber's balance per callback.
+    #[serde(default)]
+    pub notification_fee: NearToken,
+}
+
+/// A `distribute_rewards` pass that has not reached every operator yet.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardDistribution {
+    /// Pool being paid out, fixed when the distribution started.
+    pub pool: NearToken,
+    pub total_points: u64,
+    /// Position in the operator list the next page starts at.
+    pub next_index: u64,
+    pub distributed: NearToken,
+}
+
+// Operators paid per `distribute_rewards` call when no limit is given
+const DEFAULT_DISTRIBUTION_LIMIT: u32 = 100;
+
+impl Default for FeePolicy {
+    fn default() -> Self {
+        Self {
+            price_fee: NearToken::from_yoctonear(0),
+            subscription_fee: NearToken::from_yoctonear(0),
+            subscription_period: 2_592_000_000_000_000,
+            notification_fee: NearToken::from_yoctonear(0),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Oracle {
+    // Buys as many whole subscription periods as the deposit covers, for the
+    // caller or `consumer_id`, and refunds the rest.
+    #[payable]
+    pub fn subscribe(&mut self, consumer_id: Option<AccountId>) -> u64 {
+        let fee = self.fee_policy.subscription_fee;
+        assert!(!fee.is_zero(), "Subscriptions are disabled");
+        let deposit = env::attached_deposit();
+        let periods = deposit.as_yoctonear() / fee.as_yoctonear();
+        assert!(periods > 0, "Deposit below the subscription fee");
+
+        let consumer_id = consumer_id.unwrap_or_else(env::predecessor_account_id);
+        let amount = NearToken::from_yoctonear(fee.as_yoctonear() * periods);
+        let paid_until = self
+            .subscriptions
+            .get(&consumer_id)
+            .unwrap_or(0)
+            .max(env::block_timestamp())
+            .saturating_add(
+                self.fee_policy
+                    .subscription_period
+                    .saturating_mul(periods as u64),
+            );
+        self.subscriptions.insert(&consumer_id, &paid_until);
+        self.reward_pool = self.reward_pool.saturating_add(amount);
+        self.refund(deposit.saturating_sub(amount));
+
+        OracleEvent::ConsumerSubscribed {
+            consumer_id,
+            amount,
+            paid_until,
+        }
+        .emit();
+        paid_until
+    }
+
+    // On-chain price read that pays the feed's nodes. Free for subscribers,
+    // otherwise `price_fee` must be attached; any excess is refunded.
+    #[payable]
+    pub fn pull_price(&mut self, asset_id: String) -> PriceData {
+        let price = self.get_price(asset_id).expect("Price not available");
+        let excess = self.charge_price_fee(1);
+        self.refund(excess);
+        price
+    }
+
+    // Splits the reward pool between operators in proportion to the reports
+    // they had accepted into aggregates since the last distribution, paying
+    // up to `limit` operators per call. The first call fixes the pool and the
+    // points being paid out; reports accepted until the last page count
+    // towards the next distribution. Returns true once every operator has
+    // been paid. Callable by anyone.
+    pub fn distribute_rewards(&mut self, limit: Option<u32>) -> bool {
+        let limit = limit.unwrap_or(DEFAULT_DISTRIBUTION_LIMIT);
+        assert!(limit > 0, "Limit must be positive");
+        let mut distribution = match self.reward_distribution.take() {
+            Some(distribution) => distribution,
+            None => self.start_reward_distribution(),
+        };
+
+        let end = distribution
+            .next_index
+            .saturating_add(limit as u64)
+            .min(self.whitelisted_operators.len());
+        for index in distribution.next_index..end {
+            let operator_id = self.whitelisted_operators.as_vector().get(index).unwrap();
+            self.pay_distribution_share(&mut distribution, &operator_id);
+        }
+        distribution.next_index = end;
+        if end < self.whitelisted_operators.len() {
+            self.reward_distribution = Some(distribution);
+            return false;
+        }
+
+        // Rounding dust and forfeited shares go back to the pool
+        self.reward_pool = self
+            .reward_pool
+            .saturating_add(distribution.pool.saturating_sub(distribution.distributed));
+        OracleEvent::RewardsDistributed {
+            amount: distribution.distributed,
+            total_points: distribution.total_points,
+        }
+        .emit();
+        true
+    }
+
+    pub fn claim_rewards(&mut self) -> Promise {
+        let operator_id = env::predecessor_account_id();
+        let amount = self
+            .rewards
+            .remove(&operator_id)
+            .expect("No rewards to claim");
+        Promise::new(operator_id.clone()).transfer(amount).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(Gas::from_tgas(10))
+                .on_rewards_claimed(operator_id, amount),
+        )
+    }
+
+    // Restores the claimable balance when the transfer failed
+    #[private]
+    pub fn on_rewards_claimed(
+        &mut self,
+        operator_id: AccountId,
+        amount: NearToken,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) {
+        if result.is_ok() {
+            OracleEvent::RewardsClaimed {
+                operator_id,
+                amount,
+            }
+            .emit();
+            return;
+        }
+        let rewards = self.rewards.get(&operator_id).unwrap_or_default();
+        self.rewards
+            .insert(&operator_id, &rewards.saturating_add(amount));
+    }
+
+    pub fn set_fee_policy(&mut self, policy: FeePolicy) {
+        self.assert_owner();
+        self.internal_set_fee_policy(policy);
+    }
+
+    pub fn get_fee_policy(&self) -> FeePolicy {
+        self.fee_policy.clone()
+    }
+
+    /// When the consumer's subscription runs out, if it ever subscribed.
+    pub fn get_subscription(&self, consumer_id: AccountId) -> Option<u64> {
+        self.subscriptions.get(&consumer_id)
+    }
+
+    pub fn get_reward_pool(&self) -> NearToken {
+        self.reward_pool
+    }
+
+    /// Points not yet paid out, including any the running distribution has
+    /// yet to reach.
+    pub fn get_reward_points(&self, operator_id: AccountId) -> u64 {
+        self.reward_points.get(&operator_id).unwrap_or(0)
+            + self.distributing_points.get(&operator_id).unwrap_or(0)
+    }
+
+    pub fn get_reward_distribution(&self) -> Option<RewardDistribution> {
+        self.reward_distribution.clone()
+    }
+
+    pub fn get_rewards(&self, operator_id: AccountId) -> NearToken {
+        self.rewards.get(&operator_id).unwrap_or_default()
+    }
+}
+
+impl Oracle {
+    pub(crate) fn internal_set_fee_policy(&mut self, policy: FeePolicy) {
+        assert!(
+            policy.subscription_fee.is_zero() || policy.subscription_period > 0,
+            "Subscription period must be positive"
+        );
+        OracleEvent::FeePolicySet {
+            policy: policy.clone(),
+        }
+        .emit();
+        self.fee_policy = policy;
+    }
+
+    // Credits one point to the operator behind each report accepted into an
+    // aggregate.
+    pub(crate) fn credit_reward_points(&mut self, accepted: &[PriceReport]) {
+        for report in accepted {
+            let Some(operator_id) = self.node_to_operator.get(&report.oracle_id) else {
+                continue;
+            };
+            let points = self.reward_points.get(&operator_id).unwrap_or(0);
+            self.reward_points.insert(&operator_id, &(points + 1));
+            self.total_reward_points += 1;
+        }
+    }
+
+    // Undistributed points of a removed operator are dropped, leaving its
+    // share in the pool. Called before the operator leaves the list: removal
+    // moves the last operator into its place, so during a distribution that
+    // one is paid now in case the move puts it behind the next page.
+    pub(crate) fn forfeit_reward_points(&mut self, operator_id: &AccountId) {
+        if let Some(points) = self.reward_points.remove(operator_id) {
+            self.total_reward_points -= points;
+        }
+        let Some(mut distribution) = self.reward_distribution.take() else {
+            return;
+        };
+        self.distributing_points.remove(operator_id);
+        let operators = self.whitelisted_operators.as_vector();
+        if let Some(last) = operators.get(operators.len().saturating_sub(1)) {
+            self.pay_distribution_share(&mut distribution, &last);
+        }
+        self.reward_distribution = Some(distribution);
+    }
+
+    // Moves the accrued points and the pool into a new distribution, so
+    // rewards earned while it is paid out start the next one.
+    fn start_reward_distribution(&mut self) -> RewardDistribution {
+        assert!(
+            self.total_reward_points > 0,
+            "No accepted reports to reward"
+        );
+        assert!(!self.reward_pool.is_zero(), "Reward pool is empty");
+        std::mem::swap(&mut self.reward_points, &mut self.distributing_points);
+        let distribution = RewardDistribution {
+            pool: self.reward_pool,
+            total_points: self.total_reward_points,
+            next_index: 0,
+            distributed: NearToken::from_yoctonear(0),
+        };
+        self.reward_pool = NearToken::from_yoctonear(0);
+        self.total_reward_points = 0;
+        distribution
+    }
+
+    fn pay_distribution_share(
+        &mut self,
+        distribution: &mut RewardDistribution,
+        operator_id: &AccountId,
+    ) {
+        let Some(points) = self.distributing_points.remove(operator_id) else {
+            return;
+        };
+        let share = NearToken::from_yoctonear(
+            distribution.pool.as_yoctonear() * points as u128 / distribution.total_points as u128,
+        );
+        let rewards = self.rewards.get(operator_id).unwrap_or_default();
+        self.rewards
+            .insert(operator_id, &rewards.saturating_add(share));
+        distribution.distributed = distribution.distributed.saturating_add(share);
+    }
+
+    // Adds the fee for reading `assets` prices to the reward pool, unless the
+    // caller is subscribed, and returns what is left of the attached deposit.
+    pub(crate) fn charge_price_fee(&mut self, assets: usize) -> NearToken {
+        let fee = if self.is_subscribed(&env::predecessor_account_id()) {
+            NearToken::from_yoctonear(0)
+        } else {
+            self.fee_policy.price_fee.saturating_mul(assets as u128)
+        };
+        let deposit = env::attached_deposit();
+        assert!(deposit >= fee, "Attached deposit below the price fee");
+        self.reward_pool = self.reward_pool.saturating_add(fee);
+        deposit.saturating_sub(fee)
+    }
+
+    fn is_subscribed(&self, consumer_id: &AccountId) -> bool {
+        self.subscriptions
+            .get(consumer_id)
+            .is_some_and(|paid_until| paid_until > env::block_timestamp())
+    }
+
+    fn refund(&self, amount: NearToken) {
+        if !amount.is_zero() {
+            Promise::new(env::predecessor_account_id()).transfer(amount);
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use crate::*;
+use near_sdk::PromiseError;
+
+/// NEAR bonded by an operator. Unbonding funds stay slashable until they are
+/// withdrawn.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OperatorBond {
+    pub bonded: NearToken,
+    pub unbonding: NearToken,
+    /// When `unbonding` becomes withdrawable.
+    pub withdrawable_at: u64,
+    /// Timestamp of the last report slashed for deviation, so a report is
+    /// only slashed once.
+    pub last_slashed_report: Option<u64>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakingPolicy {
+    /// Bond an operator's nodes need to report. Zero disables the requirement.
+    pub min_bond: NearToken,
+    /// Delay between `unbond` and `withdraw_bond`, in nanoseconds.
+    pub unbonding_period: u64,
+    /// Deviation from a round's aggregate above which anyone may have the
+    /// reporting operator slashed. Zero disables deviation slashing.
+    pub slash_deviation_bps: u16,
+    /// Share of the operator's bond taken per slashed report.
+    pub slash_bps: u16,
+}
+
+impl Default for StakingPolicy {
+    fn default() -> Self {
+        Self {
+            min_bond: NearToken::from_yoctonear(0),
+            unbonding_period: 604_800_000_000_000,
+            slash_deviation_bps: 0,
+            slash_bps: 1_000,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Oracle {
+    #[payable]
+    pub fn deposit_bond(&mut self) {
+        let operator_id = env::predecessor_account_id();
+        assert!(
+            self.whitelisted_operators.contains(&operator_id),
+            "Not a whitelisted operator"
+        );
+        let amount = env::attached_deposit();
+        assert!(!amount.is_zero(), "Attach a deposit to bond");
+
+        let mut bond = self.bonds.get(&operator_id).unwrap_or_default();
+        bond.bonded = bond.bonded.saturating_add(amount);
+        self.bonds.insert(&operator_id, &bond);
+        OracleEvent::BondDeposited {
+            operator_id,
+            amount,
+            bonded: bond.bonded,
+        }
+        .emit();
+    }
+
+    // Starts unbonding `amount`. Further unbonding restarts the period for the
+    // whole unbonding balance.
+    pub fn unbond(&mut self, amount: NearToken) {
+        let operator_id = env::predecessor_account_id();
+        let mut bond = self.bonds.get(&operator_id).expect("No bond");
+        assert!(!amount.is_zero(), "Amount must be positive");
+        assert!(amount <= bond.bonded, "Amount exceeds bonded balance");
+
+        bond.bonded = bond.bonded.saturating_sub(amount);
+        bond.unbonding = bond.unbonding.saturating_add(amount);
+        bond.withdrawable_at =
+            env::block_timestamp().saturating_add(self.staking_policy.unbonding_period);
+        self.bonds.insert(&operator_id, &bond);
+        OracleEvent::BondUnbonding {
+            operator_id,
+            amount,
+            withdrawable_at: bond.withdrawable_at,
+        }
+        .emit();
+    }
+
+    pub fn withdraw_bond(&mut self) -> Promise {
+        let operator_id = env::predecessor_account_id();
+        let mut bond = self.bonds.get(&operator_id).expect("No bond");
+        assert!(!bond.unbonding.is_zero(), "Nothing to withdraw");
+        assert!(
+            env::block_timestamp() >= bond.withdrawable_at,
+            "Bond is still unbonding"
+        );
+
+        let amount = bond.unbonding;
+        bond.unbonding = NearToken::from_yoctonear(0);
+        self.store_bond(&operator_id, bond);
+        Promise::new(operator_id.clone()).transfer(amount).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(Gas::from_tgas(10))
+                .on_bond_withdrawn(operator_id, amount),
+        )
+    }
+
+    // Restores the unbonding balance when the transfer failed
+    #[private]
+    pub fn on_bond_withdrawn(
+        &mut self,
+        operator_id: AccountId,
+        amount: NearToken,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) {
+        if result.is_ok() {
+            OracleEvent::BondWithdrawn {
+                operator_id,
+                amount,
+            }
+            .emit();
+            return;
+        }
+        let mut bond = self.bonds.get(&operator_id).unwrap_or_default();
+        bond.unbonding = bond.unbonding.saturating_add(amount);
+        self.bonds.insert(&operator_id, &bond);
+    }
+
+    // Slashes the operator of `node_account` when its report in the asset's
+    // latest aggregated round deviates from the aggregate by more than
+    // `slash_deviation_bps`. Callable by anyone.
+    pub fn slash_deviation(&mut self, asset_id: String, node_account: AccountId) {
+        let threshold = self.staking_policy.slash_deviation_bps;
+        assert!(threshold > 0, "Deviation slashing is disabled");
+        let price = self
+            .aggregated_prices
+            .get(&asset_id)
+            .expect("No aggregated price");
+        let report = self
+            .price_reports
+            .get(&asset_id)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|r| r.oracle_id == node_account && r.price.round_id == price.round_id)
+            .expect("No report in the aggregated round");
+        let deviation = deviation_bps(report.price.multiplier, price.multiplier);
+        assert!(
+            deviation > threshold as u128,
+            "Report is within the allowed deviation"
+        );
+
+        let operator_id = self
+            .node_to_operator
+            .get(&node_account)
+            .expect("Node account not set by any operator");
+        let mut bond = self.bonds.get(&operator_id).expect("Operator has no bond");
+        assert!(
+            bond.last_slashed_report
+                .is_none_or(|last| report.timestamp > last),
+            "Report already slashed"
+        );
+        // Unbonding funds are slashable too, so unbonding after a bad report
+        // doesn't escape the penalty
+        let stake = bond.bonded.saturating_add(bond.unbonding);
+        let amount = NearToken::from_yoctonear(
+            stake.as_yoctonear() * self.staking_policy.slash_bps as u128 / 10_000,
+        );
+        assert!(!amount.is_zero(), "Nothing to slash");
+        bond.last_slashed_report = Some(report.timestamp);
+        self.bonds.insert(&operator_id, &bond);
+
+        self.internal_slash_operator(
+            &operator_id,
+            amount,
+            format!("Deviation of {} bps on {}", deviation, asset_id),
+        );
+    }
+
+    pub fn slash_operator(&mut self, operator_id: AccountId, amount: NearToken, reason: String) {
+        self.assert_owner();
+        self.internal_slash_operator(&operator_id, amount, reason);
+    }
+
+    pub fn set_staking_policy(&mut self, policy: StakingPolicy) {
+        self.assert_owner();
+        self.internal_set_staking_policy(policy);
+    }
+
+    pub fn set_treasury(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        self.internal_set_treasury(account_id);
+    }
+
+    pub fn get_bond(&self, operator_id: AccountId) -> Option<OperatorBond> {
+        self.bonds.get(&operator_id)
+    }
+
+    pub fn get_staking_policy(&self) -> StakingPolicy {
+        self.staking_policy.clone()
+    }
+
+    pub fn get_treasury(&self) -> AccountId {
+        self.treasury.clone()
+    }
+}
+
+impl Oracle {
+    // Takes `amount` from the bonded balance, then from unbonding funds, and
+    // sends it to the treasury.
+    pub(crate) fn internal_slash_operator(
+        &mut self,
+        operator_id: &AccountId,
+        amount: NearToken,
+        reason: String,
+    ) {
+        let mut bond = self.bonds.get(operator_id).expect("Operator has no bond");
+        let from_bonded = amount.min(bond.bonded);
+        let from_unbonding = amount.saturating_sub(from_bonded).min(bond.unbonding);
+        bond.bonded = bond.bonded.saturating_sub(from_bonded);
+        bond.unbonding = bond.unbonding.saturating_sub(from_unbonding);
+        self.store_bond(operator_id, bond);
+
+        let slashed = from_bonded.saturating_add(from_unbonding);
+        if slashed.is_zero() {
+            return;
+        }
+        OracleEvent::OperatorSlashed {
+            operator_id: operator_id.clone(),
+            amount: slashed,
+            treasury: self.treasury.clone(),
+            reason,
+        }
+        .emit();
+        Promise::new(self.treasury.clone()).transfer(slashed);
+    }
+
+    pub(crate) fn internal_set_staking_policy(&mut self, policy: StakingPolicy) {
+        assert!(
+            policy.slash_bps <= 10_000,
+            "Slash must be <= 10000 basis points"
+        );
+        OracleEvent::StakingPolicySet {
+            policy: policy.clone(),
+        }
+        .emit();
+        self.staking_policy = policy;
+    }
+
+    pub(crate) fn internal_set_treasury(&mut self, account_id: AccountId) {
+        self.treasury = account_id.clone();
+        OracleEvent::TreasurySet { account_id }.emit();
+    }
+
+    // Whether the operator running the node meets the minimum bond
+    pub(crate) fn is_bonded(&self, node_account: &AccountId) -> bool {
+        let min_bond = self.staking_policy.min_bond;
+        if min_bond.is_zero() {
+            return true;
+        }
+        self.node_to_operator
+            .get(node_account)
+            .and_then(|operator_id| self.bonds.get(&operator_id))
+            .is_some_and(|bond| bond.bonded >= min_bond)
+    }
+
+    pub(crate) fn bonded_amount(&self, node_account: &AccountId) -> u128 {
+        self.node_to_operator
+            .get(node_account)
+            .and_then(|operator_id| self.bonds.get(&operator_id))
+            .map_or(0, |bond| bond.bonded.as_yoctonear())
+    }
+
+    fn store_bond(&mut self, operator_id: &AccountId, bond: OperatorBond) {
+        if bond.bonded.is_zero() && bond.unbonding.is_zero() {
+            self.bonds.remove(operator_id);
+        } else {
+            self.bonds.insert(operator_id, &bond);
+        }
+    }
+}
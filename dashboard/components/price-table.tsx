@@ -30,7 +30,7 @@ interface Asset {
   symbol: string;
   name: string;
   decimals: number;
-  active: boolean;
+  status: 'Active' | 'ReportingOnly' | 'QueryFrozen' | 'Delisted';
   min_sources: number;
 }
 
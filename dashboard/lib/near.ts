@@ -2,12 +2,14 @@ export interface Price {
   multiplier: string;
   decimals: number;
   timestamp: number;
+  conf?: string;
 }
 
 export interface PriceData {
   asset_id: string;
   price: Price;
   num_sources: number;
+  round_id?: number;
 }
 
 export interface Asset {
@@ -15,7 +17,7 @@ export interface Asset {
   symbol: string;
   name: string;
   decimals: number;
-  active: boolean;
+  status: 'Active' | 'ReportingOnly' | 'QueryFrozen' | 'Delisted';
   min_sources: number;
 }
 
@@ -26,6 +28,10 @@ export interface OracleNode {
   code_hash: string;
   last_report: number;
   active: boolean;
+  suspended_until?: number;
+  attested_at?: number;
+  measurement?: string;
+  signing_key?: string | null;
 }
 
 const NEAR_CONFIG = {
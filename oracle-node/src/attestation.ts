@@ -0,0 +1,38 @@
+import { createHash } from 'crypto';
+import { existsSync, readFileSync, writeFileSync } from 'fs';
+
+// Gramine exposes the enclave's quoting interface as pseudo-files
+const GRAMINE_USER_REPORT_DATA = '/dev/attestation/user_report_data';
+const GRAMINE_QUOTE = '/dev/attestation/quote';
+
+/**
+ * Report data expected by the oracle contract:
+ * sha256(key type byte || public key) || issued_at (u64 LE, ns) || zero padding
+ */
+export function buildReportData(keyType: number, publicKey: Uint8Array, issuedAtNs: bigint): Buffer {
+  const reportData = Buffer.alloc(64);
+  createHash('sha256')
+    .update(Buffer.concat([Buffer.from([keyType]), Buffer.from(publicKey)]))
+    .digest()
+    .copy(reportData, 0);
+  reportData.writeBigUInt64LE(issuedAtNs, 32);
+  return reportData;
+}
+
+/**
+ * Produces a DCAP quote over `reportData`. Inside a Gramine enclave the quote
+ * is generated on the spot; otherwise a pre-generated quote is read from
+ * `quoteFile`, which must already embed matching report data.
+ */
+export function generateQuote(reportData: Buffer, quoteFile?: string): Buffer {
+  if (existsSync(GRAMINE_USER_REPORT_DATA)) {
+    writeFileSync(GRAMINE_USER_REPORT_DATA, reportData);
+    return readFileSync(GRAMINE_QUOTE);
+  }
+
+  if (quoteFile) {
+    return readFileSync(quoteFile);
+  }
+
+  throw new Error('No TEE quoting interface available; set TEE_QUOTE_FILE');
+}
@@ -22,8 +22,8 @@ export interface Config {
   updateInterval: number;
   assets: AssetConfig[];
   codeHash: string;
-  mrEnclave: string;
-  attestationIssuedAtMs?: number;
+  quoteFile?: string;
+  reattestInterval: number;
 }
 
 export const DEFAULT_CONFIG: Partial<Config> = {
@@ -31,7 +31,6 @@ export const DEFAULT_CONFIG: Partial<Config> = {
   nearNetworkId: 'testnet',
   nearNodeUrl: 'https://rpc.testnet.near.org',
   updateInterval: 60000, // 60 seconds
-  mrEnclave: 'dev_mr_enclave',
   assets: [
     {
       id: 'near',
@@ -209,15 +208,6 @@ export const DEFAULT_CONFIG: Partial<Config> = {
 };
 
 export function loadConfig(): Config {
-  const attestationIssuedAtMsEnv = process.env.ATTESTATION_ISSUED_AT_MS;
-  const parsedAttestationMs = attestationIssuedAtMsEnv
-    ? Number.parseInt(attestationIssuedAtMsEnv, 10)
-    : undefined;
-
-  if (parsedAttestationMs !== undefined && Number.isNaN(parsedAttestationMs)) {
-    throw new Error('ATTESTATION_ISSUED_AT_MS must be a valid integer (milliseconds)');
-  }
-
   const config = {
     ...DEFAULT_CONFIG,
     nearNetwork: (process.env.NEAR_NETWORK as 'testnet' | 'mainnet') || 'testnet',
@@ -228,17 +218,13 @@ export function loadConfig(): Config {
     privateKey: process.env.NODE_PRIVATE_KEY || '',
     updateInterval: Number.parseInt(process.env.UPDATE_INTERVAL || '60000', 10),
     codeHash: process.env.CODE_HASH || 'dev_hash',
-    mrEnclave: process.env.TEE_MR_ENCLAVE || DEFAULT_CONFIG.mrEnclave || '',
-    attestationIssuedAtMs: parsedAttestationMs,
+    quoteFile: process.env.TEE_QUOTE_FILE || undefined,
+    reattestInterval: Number.parseInt(process.env.REATTEST_INTERVAL || '86400000', 10),
   } as Config;
 
   if (!config.privateKey) {
     throw new Error('NODE_PRIVATE_KEY environment variable is required');
   }
 
-  if (!config.mrEnclave) {
-    throw new Error('TEE_MR_ENCLAVE environment variable is required');
-  }
-
   return config;
 }
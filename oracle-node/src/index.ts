@@ -1,6 +1,6 @@
 import { config as dotenvConfig } from 'dotenv';
 import { loadConfig } from './config.js';
-import { PriceFetcher } from './priceFetcher.js';
+import { PriceData, PriceFetcher } from './priceFetcher.js';
 import { NearIntegration } from './nearIntegration.js';
 
 // Load environment variables
@@ -12,6 +12,7 @@ class OracleNode {
   private near = new NearIntegration(this.config);
   private running = false;
   private updateTimer: NodeJS.Timeout | null = null;
+  private reattestTimer: NodeJS.Timeout | null = null;
 
   async start(): Promise<void> {
     console.log('Starting TEE Oracle Node...');
@@ -22,20 +23,29 @@ class OracleNode {
       // Initialize NEAR connection
       await this.near.initialize();
 
-      // Check registration status
+      // Register on every start: the enclave signing key is regenerated
       const isRegistered = await this.near.checkRegistration();
-      if (!isRegistered) {
-        console.log('Node not registered. Attempting registration...');
-        await this.near.registerNode();
-      } else {
-        console.log('Node is already registered');
-      }
+      console.log(
+        isRegistered
+          ? 'Node is already registered, registering new signing key...'
+          : 'Node not registered. Attempting registration...'
+      );
+      await this.near.registerNode();
 
       // Start main loop
       this.running = true;
       await this.updatePrices();
       this.scheduleNextUpdate();
 
+      // Re-attest before the contract's reattestation window lapses
+      this.reattestTimer = setInterval(async () => {
+        try {
+          await this.near.registerNode();
+        } catch (error: any) {
+          console.error('Error re-attesting node:', error?.message || error);
+        }
+      }, this.config.reattestInterval);
+
       console.log('Oracle node started successfully');
     } catch (error) {
       console.error('Failed to start oracle node:', error);
@@ -46,12 +56,14 @@ class OracleNode {
   private async updatePrices(): Promise<void> {
     console.log(`\n[${new Date().toISOString()}] Updating prices...`);
 
+    const batch: { priceData: PriceData; decimals: number }[] = [];
+
     for (const asset of this.config.assets) {
       try {
         const priceData = await this.fetcher.fetchPrice(asset);
 
         if (priceData) {
-          await this.near.reportPrice(priceData, asset.decimals);
+          batch.push({ priceData, decimals: asset.decimals });
         } else {
           console.warn(`Failed to fetch price for ${asset.symbol}`);
         }
@@ -63,6 +75,15 @@ class OracleNode {
       await this.sleep(1000);
     }
 
+    // Submit all fetched prices in a single transaction
+    if (batch.length > 0) {
+      try {
+        await this.near.reportPrices(batch);
+      } catch (error: any) {
+        console.error('Error reporting prices:', error?.message || error);
+      }
+    }
+
     console.log('Price update cycle completed');
   }
 
@@ -88,6 +109,11 @@ class OracleNode {
       this.updateTimer = null;
     }
 
+    if (this.reattestTimer) {
+      clearInterval(this.reattestTimer);
+      this.reattestTimer = null;
+    }
+
     console.log('Oracle node stopped');
   }
 }
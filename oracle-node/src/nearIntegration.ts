@@ -1,6 +1,8 @@
 import * as nearAPI from 'near-api-js';
 import { Config } from './config.js';
 import { PriceData } from './priceFetcher.js';
+import { buildReportData, generateQuote } from './attestation.js';
+import { encodeSignedPriceReport, signedReportArgs } from './signing.js';
 
 const { connect, KeyPair, keyStores } = nearAPI;
 
@@ -9,6 +11,10 @@ export class NearIntegration {
   private account: nearAPI.Account | null = null;
   private contractId: string;
   private config: Config;
+  private keyPair: nearAPI.KeyPair | null = null;
+  // Generated in enclave memory on every start and never leaves it
+  private signingKey = KeyPair.fromRandom('ed25519');
+  private lastNonce = 0n;
 
   constructor(config: Config) {
     this.config = config;
@@ -18,6 +24,7 @@ export class NearIntegration {
   async initialize(): Promise<void> {
     const keyStore = new keyStores.InMemoryKeyStore();
     const keyPair = KeyPair.fromString(this.config.privateKey as any);
+    this.keyPair = keyPair;
     await keyStore.setKey(
       this.config.nearNetworkId,
       this.config.nodeAccountId,
@@ -68,8 +75,11 @@ export class NearIntegration {
     console.log(`Registering node with code hash: ${this.config.codeHash}`);
 
     try {
-      const issuedAtMs = this.config.attestationIssuedAtMs ?? Date.now();
-      const issuedAtNs = Number(BigInt(Math.max(0, issuedAtMs)) * 1_000_000n);
+      // Bind the quote to the enclave signing key
+      const publicKey = this.signingKey.getPublicKey();
+      const issuedAtNs = BigInt(Date.now()) * 1_000_000n;
+      const reportData = buildReportData(publicKey.keyType, publicKey.data, issuedAtNs);
+      const quote = generateQuote(reportData, this.config.quoteFile);
 
       const result = await this.account.functionCall({
         contractId: this.contractId,
@@ -77,11 +87,11 @@ export class NearIntegration {
         args: {
           code_hash: this.config.codeHash,
           attestation: {
-            mr_enclave: this.config.mrEnclave,
-            issued_at: issuedAtNs,
+            quote: quote.toString('base64'),
           },
+          signing_key: publicKey.toString(),
         },
-        gas: BigInt('30000000000000'), // 30 TGas
+        gas: BigInt('300000000000000'), // 300 TGas for quote verification
       });
 
       console.log('Node registered successfully:', result);
@@ -120,6 +130,53 @@ export class NearIntegration {
     }
   }
 
+  async reportPrices(
+    prices: { priceData: PriceData; decimals: number }[]
+  ): Promise<void> {
+    if (!this.account) {
+      throw new Error('NEAR not initialized');
+    }
+
+    const items = prices.map(({ priceData, decimals }) => ({
+      asset_id: priceData.assetId,
+      multiplier: BigInt(Math.floor(priceData.price * Math.pow(10, decimals))),
+      decimals,
+    }));
+
+    // Nonces must increase; nanosecond timestamps do so across restarts
+    const signedAt = BigInt(Date.now()) * 1_000_000n;
+    this.lastNonce = signedAt > this.lastNonce ? signedAt : this.lastNonce + 1n;
+    const report = {
+      contract_id: this.contractId,
+      node_account: this.config.nodeAccountId,
+      nonce: this.lastNonce,
+      signed_at: signedAt,
+      prices: items,
+    };
+    const { signature } = this.signingKey.sign(encodeSignedPriceReport(report));
+
+    try {
+      const outcome = await this.account.functionCall({
+        contractId: this.contractId,
+        methodName: 'report_price_signed',
+        args: signedReportArgs(report, signature),
+        gas: BigInt('300000000000000'), // 300 TGas
+      });
+
+      const result = nearAPI.providers.getTransactionLastResult(outcome) as {
+        accepted: string[];
+        rejected: { asset_id: string; reason: string }[];
+      };
+      console.log(`Prices reported: ${result.accepted.join(', ') || 'none'}`);
+      for (const rejected of result.rejected) {
+        console.warn(`Price rejected for ${rejected.asset_id}: ${rejected.reason}`);
+      }
+    } catch (error: any) {
+      console.error('Failed to report prices:', error?.message || error);
+      throw error;
+    }
+  }
+
   async getPrice(assetId: string): Promise<any> {
     if (!this.account) {
       throw new Error('NEAR not initialized');
@@ -0,0 +1,74 @@
+export interface SignedPriceItem {
+  asset_id: string;
+  multiplier: bigint;
+  decimals: number;
+}
+
+export interface SignedPriceReport {
+  contract_id: string;
+  node_account: string;
+  nonce: bigint;
+  signed_at: bigint;
+  prices: SignedPriceItem[];
+}
+
+function encodeString(value: string): Buffer {
+  const bytes = Buffer.from(value, 'utf8');
+  const length = Buffer.alloc(4);
+  length.writeUInt32LE(bytes.length);
+  return Buffer.concat([length, bytes]);
+}
+
+function encodeU64(value: bigint): Buffer {
+  const bytes = Buffer.alloc(8);
+  bytes.writeBigUInt64LE(value);
+  return bytes;
+}
+
+function encodeU128(value: bigint): Buffer {
+  const mask = (1n << 64n) - 1n;
+  return Buffer.concat([encodeU64(value & mask), encodeU64(value >> 64n)]);
+}
+
+/**
+ * Borsh encoding of the contract's `SignedPriceReport`, which is what the
+ * enclave signing key signs.
+ */
+export function encodeSignedPriceReport(report: SignedPriceReport): Buffer {
+  const count = Buffer.alloc(4);
+  count.writeUInt32LE(report.prices.length);
+
+  return Buffer.concat([
+    encodeString(report.contract_id),
+    encodeString(report.node_account),
+    encodeU64(report.nonce),
+    encodeU64(report.signed_at),
+    count,
+    ...report.prices.map((item) =>
+      Buffer.concat([encodeString(item.asset_id), encodeU128(item.multiplier), Buffer.from([item.decimals])])
+    ),
+  ]);
+}
+
+/**
+ * JSON arguments for `report_price_signed`. Built by hand so 64-bit and
+ * 128-bit integers keep their exact value.
+ */
+export function signedReportArgs(report: SignedPriceReport, signature: Uint8Array): Buffer {
+  const prices = report.prices
+    .map(
+      (item) =>
+        `{"asset_id":${JSON.stringify(item.asset_id)},"multiplier":${item.multiplier.toString()},"decimals":${item.decimals}}`
+    )
+    .join(',');
+  const payload =
+    `{"contract_id":${JSON.stringify(report.contract_id)},` +
+    `"node_account":${JSON.stringify(report.node_account)},` +
+    `"nonce":${report.nonce.toString()},` +
+    `"signed_at":${report.signed_at.toString()},` +
+    `"prices":[${prices}]}`;
+
+  return Buffer.from(
+    `{"payload":${payload},"signature":"${Buffer.from(signature).toString('base64')}"}`
+  );
+}
@@ -91,19 +91,19 @@ echo ""
 # Step 7: Add assets
 echo -e "${YELLOW}Step 7: Adding assets to contract...${NC}"
 
-near contract call-function as-transaction "$CONTRACT_ACCOUNT" add_asset json-args '{"asset":{"id":"near","symbol":"NEAR","name":"NEAR Protocol","decimals":4,"active":true,"min_sources":2}}' prepaid-gas '30.0 Tgas' attached-deposit '0 NEAR' sign-as "$CONTRACT_ACCOUNT" network-config testnet sign-with-keychain send
+near contract call-function as-transaction "$CONTRACT_ACCOUNT" add_asset json-args '{"asset":{"id":"near","symbol":"NEAR","name":"NEAR Protocol","decimals":4,"status":"Active","min_sources":2}}' prepaid-gas '30.0 Tgas' attached-deposit '0 NEAR' sign-as "$CONTRACT_ACCOUNT" network-config testnet sign-with-keychain send
 
 
 
-near contract call-function as-transaction "$CONTRACT_ACCOUNT" add_asset json-args '{"asset":{"id":"bitcoin","symbol":"BTC","name":"Bitcoin","decimals":4,"active":true,"min_sources":2}}' prepaid-gas '30.0 Tgas' attached-deposit '0 NEAR' sign-as "$CONTRACT_ACCOUNT" network-config testnet sign-with-keychain send
+near contract call-function as-transaction "$CONTRACT_ACCOUNT" add_asset json-args '{"asset":{"id":"bitcoin","symbol":"BTC","name":"Bitcoin","decimals":4,"status":"Active","min_sources":2}}' prepaid-gas '30.0 Tgas' attached-deposit '0 NEAR' sign-as "$CONTRACT_ACCOUNT" network-config testnet sign-with-keychain send
 
 
 
-near contract call-function as-transaction "$CONTRACT_ACCOUNT" add_asset json-args '{"asset":{"id":"ethereum","symbol":"ETH","name":"Ethereum","decimals":4,"active":true,"min_sources":2}}' prepaid-gas '30.0 Tgas' attached-deposit '0 NEAR' sign-as "$CONTRACT_ACCOUNT" network-config testnet sign-with-keychain send
+near contract call-function as-transaction "$CONTRACT_ACCOUNT" add_asset json-args '{"asset":{"id":"ethereum","symbol":"ETH","name":"Ethereum","decimals":4,"status":"Active","min_sources":2}}' prepaid-gas '30.0 Tgas' attached-deposit '0 NEAR' sign-as "$CONTRACT_ACCOUNT" network-config testnet sign-with-keychain send
 
 
 
-near contract call-function as-transaction "$CONTRACT_ACCOUNT" add_asset json-args '{"asset":{"id":"usdc","symbol":"USDC","name":"USD Coin","decimals":4,"active":true,"min_sources":2}}' prepaid-gas '30.0 Tgas' attached-deposit '0 NEAR' sign-as "$CONTRACT_ACCOUNT" network-config testnet sign-with-keychain send
+near contract call-function as-transaction "$CONTRACT_ACCOUNT" add_asset json-args '{"asset":{"id":"usdc","symbol":"USDC","name":"USD Coin","decimals":4,"status":"Active","min_sources":2}}' prepaid-gas '30.0 Tgas' attached-deposit '0 NEAR' sign-as "$CONTRACT_ACCOUNT" network-config testnet sign-with-keychain send
 
 echo -e "${GREEN}✓ Assets added${NC}"
 echo ""
@@ -116,7 +116,18 @@ near contract call-function as-transaction "$CONTRACT_ACCOUNT" approve_code_hash
 
 
 
-near contract call-function as-transaction "$CONTRACT_ACCOUNT" approve_attestation json-args "{\"code_hash\":\"$CONTRACT_HASH\",\"mr_enclave\":\"dev_mr_enclave_v1\"}" prepaid-gas '30.0 Tgas' attached-deposit '0 NEAR' sign-as "$CONTRACT_ACCOUNT" network-config testnet sign-with-keychain send
+# Hex MRENCLAVE (SGX) or MRTD (TDX) of the oracle node build
+TEE_MR_ENCLAVE="${TEE_MR_ENCLAVE:-0000000000000000000000000000000000000000000000000000000000000000}"
+near contract call-function as-transaction "$CONTRACT_ACCOUNT" approve_attestation json-args "{\"code_hash\":\"$CONTRACT_HASH\",\"mr_enclave\":\"$TEE_MR_ENCLAVE\"}" prepaid-gas '30.0 Tgas' attached-deposit '0 NEAR' sign-as "$CONTRACT_ACCOUNT" network-config testnet sign-with-keychain send
+
+# Intel SGX Provisioning Certification Root CA (DER), trusted for quote verification
+ROOT_CA_FILE="${INTEL_ROOT_CA_FILE:-$PROJECT_DIR/Intel_SGX_Provisioning_Certification_RootCA.cer}"
+if [ -f "$ROOT_CA_FILE" ]; then
+    ROOT_CA_B64=$(base64 < "$ROOT_CA_FILE" | tr -d '\n')
+    near contract call-function as-transaction "$CONTRACT_ACCOUNT" approve_root_certificate json-args "{\"cert\":\"$ROOT_CA_B64\"}" prepaid-gas '30.0 Tgas' attached-deposit '0 NEAR' sign-as "$CONTRACT_ACCOUNT" network-config testnet sign-with-keychain send
+else
+    echo -e "${YELLOW}No root CA at $ROOT_CA_FILE; nodes cannot register until one is approved${NC}"
+fi
 
 echo -e "${GREEN}✓ Code hash and attestation approved${NC}"
 echo ""
@@ -159,7 +170,6 @@ NODE_ACCOUNT_ID=$NODE_ACCOUNT
 NODE_PRIVATE_KEY=$NODE_PRIVATE_KEY
 ORACLE_CONTRACT_ID=$CONTRACT_ACCOUNT
 CODE_HASH=$CONTRACT_HASH
-TEE_MR_ENCLAVE=dev_mr_enclave_v1
 NEAR_NETWORK=testnet
 NEAR_NODE_URL=https://rpc.testnet.fastnear.com
 UPDATE_INTERVAL=60000